@@ -0,0 +1,60 @@
+use anyhop::{Goal, Method, Operator};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+use satellite_numeric::generator::{generate, GeneratorConfig};
+use satellite_numeric::methods::SatelliteMethod;
+use satellite_numeric::operators::{Heuristic, SatelliteEnum, SatelliteGoals, SatelliteOperator, SatelliteState};
+use satellite_numeric::pddl_parser::make_satellite_problem_from;
+use satellite_numeric::plan_io::parse_plan;
+
+const PFILE1_PLAN: &str = "(switch_on instrument0 satellite0)
+(turn_to satellite0 groundstation2 phenomenon6)
+(calibrate satellite0 instrument0 groundstation2)
+(turn_to satellite0 phenomenon6 groundstation2)
+(take_image satellite0 phenomenon6 instrument0 thermograph0)
+(turn_to satellite0 star5 phenomenon6)
+(take_image satellite0 star5 instrument0 thermograph0)
+(turn_to satellite0 phenomenon3 star5)
+(take_image satellite0 phenomenon3 instrument0 thermograph0)
+(turn_to satellite0 phenomenon4 phenomenon3)
+(take_image satellite0 phenomenon4 instrument0 thermograph0)";
+
+//pfile1 and a larger generated problem, each with a plan that solves it.
+fn problems() -> Vec<(&'static str, SatelliteState, SatelliteGoals, Vec<SatelliteOperator<SatelliteEnum>>)> {
+    let (state, goals, objects) = make_satellite_problem_from("pfiles/pfile1").unwrap();
+    let pfile1_plan = parse_plan(PFILE1_PLAN, &objects).unwrap();
+    let generated = generate(&GeneratorConfig { satellites: 5, max_instruments: 3, modes: 5, targets: 30, observations: 40, seed: 1, ..GeneratorConfig::default() }).unwrap();
+    vec![("pfile1", state, goals, pfile1_plan),
+         ("generated", generated.state, generated.goals, generated.witness.unwrap())]
+}
+
+fn attempt_update(c: &mut Criterion) {
+    for (name, state, _, plan) in problems() {
+        c.bench_function(format!("attempt_update {}", name).as_str(), |b| b.iter_batched(
+            || state.clone(),
+            |mut state| {
+                for step in plan.iter() {
+                    black_box(step.attempt_update(&mut state));
+                }
+            },
+            BatchSize::SmallInput));
+    }
+}
+
+fn method_apply(c: &mut Criterion) {
+    for (name, state, goals, _) in problems() {
+        c.bench_function(format!("ScheduleAll apply {}", name).as_str(), |b| b.iter(|| black_box(SatelliteMethod::ScheduleAll.apply(&state, &goals))));
+    }
+}
+
+fn distance_from(c: &mut Criterion) {
+    for (name, mut state, goals, _) in problems() {
+        for heuristic in [Heuristic::GoalCount, Heuristic::Admissible, Heuristic::Fast].iter() {
+            state.config.heuristic = *heuristic;
+            c.bench_function(format!("distance_from {} {}", heuristic, name).as_str(), |b| b.iter(|| black_box(goals.distance_from(&state))));
+        }
+    }
+}
+
+criterion_group!(benches, attempt_update, method_apply, distance_from);
+criterion_main!(benches);
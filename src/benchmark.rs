@@ -0,0 +1,231 @@
+use std::{fs, io};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhop::AnytimePlannerBuilder;
+use fixed::types::I40F24;
+use log::{info, warn};
+
+use crate::operators::{apply_plan, is_satellite_valid, plan_cost, DomainConfig, GoalOrdering, Heuristic, SatelliteGoals, SatelliteState};
+use crate::pddl_parser::make_satellite_problem_from;
+
+//One way of configuring the planner, written heuristic[:orderings[:branching limit]], e.g.
+//fast:nearest,key:4. Anything left out keeps its DomainConfig default.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PlannerSetup {
+    pub name: String,
+    pub heuristic: Heuristic,
+    pub orderings: Vec<GoalOrdering>,
+    pub branching_limit: Option<usize>,
+}
+
+impl Default for PlannerSetup {
+    fn default() -> Self {
+        let config = DomainConfig::default();
+        PlannerSetup { name: String::from("default"), heuristic: config.heuristic, orderings: config.orderings, branching_limit: config.branching_limit }
+    }
+}
+
+impl PlannerSetup {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut setup = PlannerSetup { name: String::from(spec), ..PlannerSetup::default() };
+        let mut parts = spec.split(':');
+        if let Some(heuristic) = parts.next().filter(|part| !part.is_empty()) {
+            setup.heuristic = Heuristic::from_str(heuristic).map_err(|_| format!("Unknown heuristic {}", heuristic))?;
+        }
+        if let Some(orderings) = parts.next().filter(|part| !part.is_empty()) {
+            setup.orderings = orderings.split(',')
+                .map(|name| GoalOrdering::from_str(name).map_err(|_| format!("Unknown ordering {}", name)))
+                .collect::<Result<Vec<GoalOrdering>, String>>()?;
+        }
+        if let Some(limit) = parts.next().filter(|part| !part.is_empty()) {
+            setup.branching_limit = Some(limit.parse::<usize>().ok().filter(|limit| *limit > 0).ok_or_else(|| format!("Bad branching limit {}", limit))?);
+        }
+        if parts.next().is_some() {
+            return Err(format!("Too many parts in {}", spec));
+        }
+        Ok(setup)
+    }
+
+    //The semantics and costs stay as the problem has them.
+    pub fn configure(&self, state: &mut SatelliteState) {
+        state.config.heuristic = self.heuristic;
+        state.config.orderings = self.orderings.clone();
+        state.config.branching_limit = self.branching_limit;
+    }
+}
+
+//How one setup did on one problem within one time limit.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BenchmarkResult {
+    pub problem: String,
+    pub setup: String,
+    pub time_limit_ms: u128,
+    //When each cheaper plan was found, in ms from the start of the search, with its cost.
+    pub costs_over_time: Vec<(u128, I40F24)>,
+    //The rest describe the cheapest plan, and are None if no plan was found.
+    pub plan_length: Option<usize>,
+    pub fuel_used: Option<I40F24>,
+    pub valid: Option<bool>,
+}
+
+impl BenchmarkResult {
+    pub fn first_solution_ms(&self) -> Option<u128> {
+        self.costs_over_time.first().map(|(ms, _)| *ms)
+    }
+
+    pub fn best_cost(&self) -> Option<I40F24> {
+        self.costs_over_time.last().map(|(_, cost)| *cost)
+    }
+
+    //Plans found after the first one, each cheaper than the one before.
+    pub fn improvements(&self) -> usize {
+        self.costs_over_time.len().saturating_sub(1)
+    }
+}
+
+//Plans from a copy of start, so one parsed problem can be run with every setup.
+pub fn run_benchmark(problem: &str, start: &SatelliteState, goal: &SatelliteGoals, setup: &PlannerSetup, time_limit_ms: u128) -> BenchmarkResult {
+    let mut start = start.clone();
+    setup.configure(&mut start);
+    info!("Benchmarking {} with {} for {}ms", problem, setup.name, time_limit_ms);
+    let outcome = AnytimePlannerBuilder::state_goal(&start, goal)
+        .time_limit_ms(time_limit_ms)
+        .verbose(0)
+        .construct();
+
+    let mut costs_over_time: Vec<(u128, I40F24)> = vec![];
+    let mut best = None;
+    for (i, plan) in outcome.get_all_plans().into_iter().enumerate() {
+        if let Some(cost) = plan_cost(&plan, &start, goal) {
+            if costs_over_time.last().map_or(true, |(_, best_cost)| cost < *best_cost) {
+                costs_over_time.push((outcome.get_discovery_time(i), cost));
+                best = Some(plan);
+            }
+        }
+    }
+    BenchmarkResult {
+        problem: String::from(problem),
+        setup: setup.name.clone(),
+        time_limit_ms,
+        costs_over_time,
+        plan_length: best.as_ref().map(|plan| plan.len()),
+        fuel_used: best.as_ref().and_then(|plan| apply_plan(plan, &start)).map(|end| end.fuel_used),
+        valid: best.as_ref().map(|plan| is_satellite_valid(plan, &start, goal)),
+    }
+}
+
+//Runs every setup with every time limit on every file in the directory, in file name order.
+//Files that are not Satellite problems are logged and skipped.
+pub fn run_benchmarks(directory: &Path, time_limits_ms: &[u128], setups: &[PlannerSetup]) -> io::Result<Vec<BenchmarkResult>> {
+    let mut problems = vec![];
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_file() {
+            problems.push(path);
+        }
+    }
+    problems.sort();
+
+    let mut results = vec![];
+    for problem in problems.iter() {
+        let (start, goal, _) = match make_satellite_problem_from(problem.to_string_lossy().as_ref()) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                warn!("Skipping {}: {}", problem.display(), e);
+                continue;
+            }
+        };
+        let file_name = problem.file_name().map_or(problem.to_string_lossy(), |name| name.to_string_lossy());
+        for time_limit_ms in time_limits_ms.iter() {
+            for setup in setups.iter() {
+                results.push(run_benchmark(file_name.as_ref(), &start, &goal, setup, *time_limit_ms));
+            }
+        }
+    }
+    Ok(results)
+}
+
+pub const CSV_HEADER: &str = "problem,setup,time_limit_ms,first_solution_ms,best_cost,improvements,costs_over_time,plan_length,fuel_used,valid";
+
+//One row per result. Missing values are left empty, and costs_over_time lists ms:cost pairs
+//separated by spaces.
+pub fn write_csv<W: Write>(results: &[BenchmarkResult], out: &mut W) -> io::Result<()> {
+    writeln!(out, "{}", CSV_HEADER)?;
+    for result in results.iter() {
+        let costs_over_time: Vec<String> = result.costs_over_time.iter().map(|(ms, cost)| format!("{}:{}", ms, cost)).collect();
+        writeln!(out, "{},{},{},{},{},{},{},{},{},{}",
+                 csv_field(result.problem.as_str()),
+                 csv_field(result.setup.as_str()),
+                 result.time_limit_ms,
+                 optional(result.first_solution_ms()),
+                 optional(result.best_cost()),
+                 result.improvements(),
+                 costs_over_time.join(" "),
+                 optional(result.plan_length),
+                 optional(result.fuel_used),
+                 optional(result.valid))?;
+    }
+    Ok(())
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or(String::new(), |v| v.to_string())
+}
+
+//Setup names such as fast:nearest,key contain commas, so they are quoted.
+fn csv_field(text: &str) -> String {
+    if text.contains(',') || text.contains('"') {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        String::from(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pddl_parser::make_satellite_problem_from_str;
+
+    #[test]
+    fn setups_are_parsed_from_their_names() {
+        let setup = PlannerSetup::parse("fast:nearest,key:4").unwrap();
+        assert_eq!(setup.heuristic, Heuristic::Fast);
+        assert_eq!(setup.orderings, vec![GoalOrdering::NearestSlew, GoalOrdering::KeyOrder]);
+        assert_eq!(setup.branching_limit, Some(4));
+        assert_eq!(PlannerSetup::parse("::2").unwrap().orderings, PlannerSetup::default().orderings);
+        assert!(PlannerSetup::parse("fastest").is_err());
+        assert!(PlannerSetup::parse("fast:key:2:1").is_err());
+        assert!(PlannerSetup::parse("fast:key:0").is_err());
+    }
+
+    //One image, a turn away from the calibration target.
+    const TINY: &str = "(define (problem tiny) (:domain satellite)
+        (:objects satellite0 - satellite instrument0 - instrument image0 - mode star0 - direction star1 - direction)
+        (:init (on_board instrument0 satellite0) (supports instrument0 image0) (calibration_target instrument0 star0)
+               (power_avail satellite0) (pointing satellite0 star0)
+               (= (slew_time star0 star1) 10) (= (slew_time star1 star0) 10)
+               (= (fuel satellite0) 100) (= (data_capacity satellite0) 100) (= (data star1 image0) 20)
+               (= (data-stored) 0) (= (fuel-used) 0))
+        (:goal (and (have_image star1 image0))))";
+
+    #[test]
+    fn results_are_written_as_csv() {
+        let (start, goal, _) = make_satellite_problem_from_str(TINY).unwrap();
+        let setup = PlannerSetup::parse("fast:nearest").unwrap();
+        let result = run_benchmark("tiny", &start, &goal, &setup, 50);
+        assert_eq!(result.problem, "tiny");
+        assert_eq!(result.valid, Some(true));
+        assert!(result.first_solution_ms().is_some());
+
+        let mut csv = vec![];
+        write_csv(&[result], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("tiny,fast:nearest,50,"));
+        assert!(row.ends_with(",true"));
+    }
+}
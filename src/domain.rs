@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::operators::Semantics;
+use crate::parse_error::SatelliteParseError;
+use crate::pddl_parser::{canonical_name, MODELED_FUNCTIONS};
+use crate::sexpr::{atom, read_sexpr, tokenize, SExpr};
+
+//The IPC 2002 domain files that the operators implement.
+pub const STRIPS_DOMAIN: &str = include_str!("../domains/satellite.pddl");
+pub const NUMERIC_DOMAIN: &str = include_str!("../domains/satellite_numeric.pddl");
+
+pub fn domain_text(semantics: Semantics) -> &'static str {
+    match semantics {
+        Semantics::Strips => STRIPS_DOMAIN,
+        Semantics::Numeric => NUMERIC_DOMAIN,
+    }
+}
+
+//The predicates and functions a domain declares, by canonical name, each with its number of parameters.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DomainSignature {
+    pub predicates: BTreeMap<String, usize>,
+    pub functions: BTreeMap<String, usize>,
+}
+
+impl DomainSignature {
+    pub fn of(semantics: Semantics) -> Self {
+        //The bundled domains are checked by the tests below.
+        DomainSignature::parse(domain_text(semantics)).unwrap()
+    }
+
+    pub fn parse(domain: &str) -> Result<Self, SatelliteParseError> {
+        let define = read_whole(domain, "domain")?;
+        let sections = define.tagged("define").ok_or_else(|| malformed("domain", "expected (define (domain ...) ...)"))?;
+        let declared = |tag: &str| -> BTreeMap<String, usize> {
+            sections.iter()
+                .filter_map(|section| section.tagged(tag))
+                .flat_map(|declarations| declarations.iter())
+                .filter_map(|declaration| match declaration {
+                    SExpr::List(items) => Some((canonical_name(atom(items.first()?)?),
+                                                items[1..].iter().filter(|item| atom(item).map_or(false, |a| a.starts_with('?'))).count())),
+                    SExpr::Atom(_) => None,
+                })
+                .collect()
+        };
+        Ok(DomainSignature { predicates: declared(":predicates"), functions: declared(":functions") })
+    }
+}
+
+//Something in a problem that does not match the domain, with the fact it was found in.
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub enum DomainIssue {
+    UndeclaredPredicate { fact: String },
+    UndeclaredFunction { fact: String },
+    WrongArity { fact: String, expected: usize, found: usize },
+    //A declared numeric fluent that extract_state skips without an error, so it has no effect on planning.
+    Ignored { fact: String },
+}
+
+impl fmt::Display for DomainIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use DomainIssue::*;
+        match self {
+            UndeclaredPredicate { fact } => write!(f, "{}: the domain has no such predicate", fact),
+            UndeclaredFunction { fact } => write!(f, "{}: the domain has no such function", fact),
+            WrongArity { fact, expected, found } => write!(f, "{}: expected {} arguments but found {}", fact, expected, found),
+            Ignored { fact } => write!(f, "{}: ignored when the problem is read", fact),
+        }
+    }
+}
+
+//Checks the :init and :goal of a problem against the domain it belongs to: the Numeric domain if
+//:init has any numeric fluents, and the STRIPS domain otherwise.
+pub fn check_problem(contents: &str) -> Result<Vec<DomainIssue>, SatelliteParseError> {
+    check_problem_in(contents, None)
+}
+
+//Checks a problem against another domain, such as a variant of the IPC files.
+pub fn check_problem_against(domain: &DomainSignature, contents: &str) -> Result<Vec<DomainIssue>, SatelliteParseError> {
+    check_problem_in(contents, Some(domain))
+}
+
+fn check_problem_in(contents: &str, domain: Option<&DomainSignature>) -> Result<Vec<DomainIssue>, SatelliteParseError> {
+    let define = read_whole(contents, "problem")?;
+    let sections = define.tagged("define").ok_or_else(|| malformed("problem", "expected (define (problem ...) ...)"))?;
+    let init: Vec<&SExpr> = sections.iter().filter_map(|section| section.tagged(":init")).flat_map(|facts| facts.iter()).collect();
+    let goals: Vec<&SExpr> = sections.iter().filter_map(|section| section.tagged(":goal")).flat_map(|goals| goals.iter()).collect();
+    let bundled;
+    let domain = match domain {
+        Some(domain) => domain,
+        None => {
+            let numeric = init.iter().any(|fact| fact.tagged("=").is_some());
+            bundled = DomainSignature::of(if numeric { Semantics::Numeric } else { Semantics::Strips });
+            &bundled
+        }
+    };
+
+    let mut issues = vec![];
+    for fact in init {
+        match fact.tagged("=") {
+            Some(assignment) => match assignment.first() {
+                Some(function @ SExpr::List(_)) => check_function(domain, function, &mut issues),
+                _ => issues.push(DomainIssue::UndeclaredFunction { fact: fact.to_string() }),
+            },
+            None => check_predicate(domain, fact, &mut issues),
+        }
+    }
+    for goal in goals {
+        check_goal(domain, goal, &mut issues);
+    }
+    Ok(issues)
+}
+
+fn check_goal(domain: &DomainSignature, goal: &SExpr, issues: &mut Vec<DomainIssue>) {
+    match goal.tagged("and").or_else(|| goal.tagged("not")) {
+        Some(parts) => {
+            for part in parts {
+                check_goal(domain, part, issues);
+            }
+        }
+        None => check_predicate(domain, goal, issues),
+    }
+}
+
+fn check_predicate(domain: &DomainSignature, fact: &SExpr, issues: &mut Vec<DomainIssue>) {
+    let (name, args) = name_and_args(fact);
+    match domain.predicates.get(&canonical_name(&name)) {
+        Some(expected) if *expected != args => issues.push(DomainIssue::WrongArity { fact: fact.to_string(), expected: *expected, found: args }),
+        Some(_) => {}
+        None => issues.push(DomainIssue::UndeclaredPredicate { fact: fact.to_string() }),
+    }
+}
+
+fn check_function(domain: &DomainSignature, function: &SExpr, issues: &mut Vec<DomainIssue>) {
+    let (name, args) = name_and_args(function);
+    match domain.functions.get(&canonical_name(&name)) {
+        Some(expected) if *expected != args => issues.push(DomainIssue::WrongArity { fact: function.to_string(), expected: *expected, found: args }),
+        Some(_) if !MODELED_FUNCTIONS.contains(&canonical_name(&name).as_str()) => issues.push(DomainIssue::Ignored { fact: function.to_string() }),
+        Some(_) => {}
+        None => issues.push(DomainIssue::UndeclaredFunction { fact: function.to_string() }),
+    }
+}
+
+fn name_and_args(fact: &SExpr) -> (String, usize) {
+    match fact {
+        SExpr::List(items) => (items.first().and_then(atom).map_or(String::new(), String::from), items.len().saturating_sub(1)),
+        SExpr::Atom(a) => (a.clone(), 0),
+    }
+}
+
+fn read_whole(text: &str, what: &str) -> Result<SExpr, SatelliteParseError> {
+    let tokens = tokenize(text);
+    read_sexpr(&tokens).map(|(sexpr, _)| sexpr).map_err(|reason| malformed(what, reason.as_str()))
+}
+
+fn malformed(what: &str, reason: &str) -> SatelliteParseError {
+    SatelliteParseError::Malformed { item: String::from(what), reason: String::from(reason) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn bundled_domains_declare_what_the_operators_use() {
+        let strips = DomainSignature::parse(STRIPS_DOMAIN).unwrap();
+        let numeric = DomainSignature::parse(NUMERIC_DOMAIN).unwrap();
+        assert_eq!(strips.predicates.len(), 8);
+        assert_eq!(strips.predicates.get("on_board"), Some(&2));
+        assert!(strips.functions.is_empty());
+        assert_eq!(numeric.predicates, strips.predicates);
+        assert_eq!(numeric.functions.get("slew_time"), Some(&2));
+        assert_eq!(numeric.functions.get("fuel_used"), Some(&0));
+        for function in numeric.functions.keys() {
+            assert!(MODELED_FUNCTIONS.contains(&function.as_str()));
+        }
+        assert_eq!(numeric.functions.len(), 6);
+    }
+
+    #[test]
+    fn typos_are_reported() {
+        assert_eq!(check_problem(&fs::read_to_string("pfiles/pfile1").unwrap()).unwrap(), vec![]);
+        let problem = "(define (problem typos) (:domain satellite)
+            (:objects satellite0 - satellite instrument0 - instrument star0 - direction image0 - mode)
+            (:init (on-board instrument0 satellite0) (onboard instrument0 satellite0) (power_avail satellite0 instrument0)
+                   (= (fuel satellite0) 10) (= (Slew-Time star0 star0) 1) (= (slewtime star0 star0) 1))
+            (:goal (and (have_image star0 image0) (not (calibrated instrument0)))))";
+        assert_eq!(check_problem(problem).unwrap(), vec![
+            DomainIssue::UndeclaredPredicate { fact: String::from("(onboard instrument0 satellite0)") },
+            DomainIssue::WrongArity { fact: String::from("(power_avail satellite0 instrument0)"), expected: 1, found: 2 },
+            DomainIssue::UndeclaredFunction { fact: String::from("(slewtime star0 star0)") },
+        ]);
+    }
+
+    #[test]
+    fn declared_but_unmodeled_functions_are_ignored() {
+        let domain = DomainSignature::parse(NUMERIC_DOMAIN.replace("(fuel-used))", "(fuel-used)\n    (total-time))").as_str()).unwrap();
+        assert_eq!(domain.functions.get("total_time"), Some(&0));
+        let problem = "(define (problem timed) (:domain satellite)
+            (:objects satellite0 - satellite)
+            (:init (power_avail satellite0) (= (fuel satellite0) 10) (= (total-time) 0) (= (elapsed) 0))
+            (:goal (and)))";
+        assert_eq!(check_problem_against(&domain, problem).unwrap(), vec![
+            DomainIssue::Ignored { fact: String::from("(total-time)") },
+            DomainIssue::UndeclaredFunction { fact: String::from("(elapsed)") },
+        ]);
+        //The bundled domain does not declare it either.
+        assert_eq!(check_problem(problem).unwrap()[0], DomainIssue::UndeclaredFunction { fact: String::from("(total-time)") });
+    }
+}
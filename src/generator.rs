@@ -0,0 +1,331 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use fixed::types::I40F24;
+use log::warn;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::metric::PlanMetric;
+use crate::object_table::{ObjectTable, ObjectType};
+use crate::operators::{SatelliteEnum, SatelliteGoals, SatelliteOperator, SatelliteState, Semantics};
+use crate::operators::SatelliteOperator::{Calibrate, SwitchOff, SwitchOn, TakeImage, TurnTo};
+use crate::pddl_writer::problem_to_string;
+
+const MODE_KINDS: [&str; 4] = ["image", "spectrograph", "thermograph", "infrared"];
+const CALIBRATION_KINDS: [&str; 2] = ["star", "groundstation"];
+const TARGET_KINDS: [&str; 3] = ["star", "phenomenon", "planet"];
+
+//The parameters of the IPC 2002 satgen generator. Ranges are inclusive; slew times get two
+//decimal places like the IPC files, and the other quantities are whole numbers.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GeneratorConfig {
+    pub satellites: usize,
+    //Each satellite gets between 1 and this many instruments.
+    pub max_instruments: usize,
+    pub modes: usize,
+    //Directions in all. A third of them, and at least one, are calibration targets and the
+    //rest are what the observations are of.
+    pub targets: usize,
+    //The number of have_image goals.
+    pub observations: usize,
+    pub semantics: Semantics,
+    pub fuel: (u32, u32),
+    pub capacity: (u32, u32),
+    pub image_size: (u32, u32),
+    pub slew_time: (u32, u32),
+    pub seed: u64,
+    //Makes sure some instrument supports every mode, and gives satellites enough fuel and
+    //capacity for the witness plan, within their ranges where the ranges allow it.
+    pub solvable: bool,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig { satellites: 1, max_instruments: 1, modes: 3, targets: 7, observations: 4, semantics: Semantics::Numeric,
+            fuel: (100, 250), capacity: (500, 1000), image_size: (10, 250), slew_time: (1, 60), seed: 0, solvable: true }
+    }
+}
+
+//A generated problem. For solvable problems, witness is a plan that reaches the goals without
+//any search: each image is taken by the first instrument that supports its mode.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GeneratedProblem {
+    pub state: SatelliteState,
+    pub goals: SatelliteGoals,
+    pub objects: ObjectTable,
+    pub witness: Option<Vec<SatelliteOperator<SatelliteEnum>>>,
+    //Fuel or capacity set above the top of its range because the witness needs that much.
+    pub overrides: Vec<RangeOverride>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RangeOverride {
+    pub satellite: SatelliteEnum,
+    //fuel or data_capacity
+    pub function: &'static str,
+    pub max: u32,
+    pub needed: I40F24,
+}
+
+impl GeneratedProblem {
+    pub fn to_pddl(&self, name: &str) -> String {
+        problem_to_string(name, &self.state, &self.goals, &self.objects)
+    }
+}
+
+pub fn generate(config: &GeneratorConfig) -> Result<GeneratedProblem, String> {
+    if config.satellites == 0 || config.max_instruments == 0 || config.modes == 0 {
+        return Err(String::from("There must be at least one satellite, instrument and mode"));
+    }
+    if config.targets < 2 {
+        return Err(String::from("There must be at least two targets, one of them for calibration"));
+    }
+    for (name, (min, max)) in [("fuel", config.fuel), ("capacity", config.capacity), ("image size", config.image_size), ("slew time", config.slew_time)].iter() {
+        if min > max {
+            return Err(format!("The {} range {}-{} is empty", name, min, max));
+        }
+    }
+    let calibration_count = (config.targets / 3).max(1);
+    let possible = (config.targets - calibration_count) * config.modes;
+    if config.observations > possible {
+        return Err(format!("Only {} observations are possible with {} targets and {} modes", possible, config.targets, config.modes));
+    }
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut objects = ObjectTable::new();
+    //Like the IPC files, names are numbered across their kinds, e.g. thermograph0 image1.
+    let satellites: Vec<SatelliteEnum> = (0..config.satellites)
+        .map(|i| objects.insert(format!("satellite{}", i).as_str(), ObjectType::Satellite))
+        .collect();
+    let mut onboard = BTreeMap::new();
+    let mut instruments = vec![];
+    for satellite in satellites.iter() {
+        let mut carried = vec![];
+        for _ in 0..rng.gen_range(1, config.max_instruments + 1) {
+            let instrument = objects.insert(format!("instrument{}", instruments.len()).as_str(), ObjectType::Instrument);
+            instruments.push(instrument);
+            carried.push(instrument);
+        }
+        onboard.insert(*satellite, carried);
+    }
+    let modes: Vec<SatelliteEnum> = (0..config.modes)
+        .map(|i| objects.insert(format!("{}{}", MODE_KINDS.choose(&mut rng).unwrap(), i).as_str(), ObjectType::Mode))
+        .collect();
+    let directions: Vec<SatelliteEnum> = (0..config.targets)
+        .map(|i| {
+            let kinds: &[&str] = if i < calibration_count { &CALIBRATION_KINDS } else { &TARGET_KINDS };
+            objects.insert(format!("{}{}", kinds.choose(&mut rng).unwrap(), i).as_str(), ObjectType::Direction)
+        })
+        .collect();
+    let (calibration_directions, observed_directions) = directions.split_at(calibration_count);
+
+    let mut supports: BTreeMap<SatelliteEnum, Vec<SatelliteEnum>> = BTreeMap::new();
+    let mut calibration_target = BTreeMap::new();
+    for instrument in instruments.iter() {
+        let mut supported: Vec<SatelliteEnum> = modes.iter().filter(|_| rng.gen_bool(0.5)).copied().collect();
+        if supported.is_empty() {
+            supported.push(*modes.choose(&mut rng).unwrap());
+        }
+        supports.insert(*instrument, supported);
+        calibration_target.insert(*instrument, *calibration_directions.choose(&mut rng).unwrap());
+    }
+    if config.solvable {
+        for mode in modes.iter() {
+            if !supports.values().any(|supported| supported.contains(mode)) {
+                supports.get_mut(instruments.choose(&mut rng).unwrap()).unwrap().push(*mode);
+            }
+        }
+    }
+
+    let pointing: BTreeMap<SatelliteEnum, SatelliteEnum> = satellites.iter().map(|satellite| (*satellite, *directions.choose(&mut rng).unwrap())).collect();
+    let power_avail: BTreeMap<SatelliteEnum, bool> = satellites.iter().map(|satellite| (*satellite, true)).collect();
+
+    let mut data_capacity = BTreeMap::new();
+    let mut fuel = BTreeMap::new();
+    let mut image_sizes = BTreeMap::new();
+    let mut slew_time = BTreeMap::new();
+    if config.semantics == Semantics::Numeric {
+        for satellite in satellites.iter() {
+            data_capacity.insert(*satellite, whole(&mut rng, config.capacity));
+            fuel.insert(*satellite, whole(&mut rng, config.fuel));
+        }
+        for direction in observed_directions.iter() {
+            for mode in modes.iter() {
+                image_sizes.insert((*direction, *mode), whole(&mut rng, config.image_size));
+            }
+        }
+        //Turning is as slow in either direction.
+        for (i, a) in directions.iter().enumerate() {
+            for b in directions[i + 1..].iter() {
+                let time = hundredths(&mut rng, config.slew_time);
+                slew_time.insert((*a, *b), time);
+                slew_time.insert((*b, *a), time);
+            }
+        }
+    }
+
+    let mut possible_goals: Vec<(SatelliteEnum, SatelliteEnum)> = observed_directions.iter()
+        .flat_map(|direction| modes.iter().map(move |mode| (*direction, *mode)))
+        .collect();
+    possible_goals.shuffle(&mut rng);
+    let have_image: BTreeSet<(SatelliteEnum, SatelliteEnum)> = possible_goals.into_iter().take(config.observations).collect();
+
+    let zero = I40F24::from_num(0);
+    let mut state = SatelliteState::new(onboard, supports, pointing, power_avail, vec![], vec![], BTreeSet::new(), calibration_target,
+                                        data_capacity, zero, image_sizes, slew_time, zero, fuel);
+    state.set_semantics(config.semantics);
+    let metric = if config.semantics == Semantics::Numeric { Some(PlanMetric::fuel_used()) } else { None };
+    let goals = SatelliteGoals::new(have_image, BTreeMap::new(), metric);
+
+    let mut overrides = vec![];
+    let witness = if config.solvable {
+        let (plan, fuel_needed, capacity_needed) = witness_plan(&state, &goals);
+        if config.semantics == Semantics::Numeric {
+            for (satellite, needed) in fuel_needed {
+                if state.fuel[&satellite] < needed {
+                    let fuel = redraw(&mut rng, satellite, "fuel", needed, config.fuel, &mut overrides);
+                    state.set_satellite_fuel(&satellite, fuel);
+                }
+            }
+            for (satellite, needed) in capacity_needed {
+                if state.data_capacity[&satellite] < needed {
+                    let capacity = redraw(&mut rng, satellite, "data_capacity", needed, config.capacity, &mut overrides);
+                    state.set_data_capacity(satellite, capacity);
+                }
+            }
+        }
+        Some(plan)
+    } else {
+        None
+    };
+    for raised in overrides.iter() {
+        warn!("{} {} is {} although the range ends at {}, since the witness plan needs that much",
+              raised.function, objects.named(&raised.satellite), raised.needed, raised.max);
+    }
+    Ok(GeneratedProblem { state, goals, objects, witness, overrides })
+}
+
+//Draws again from the part of the range that is at least needed. If the whole range is too
+//small, gives exactly what is needed and records the override.
+fn redraw(rng: &mut StdRng, satellite: SatelliteEnum, function: &'static str, needed: I40F24, (min, max): (u32, u32), overrides: &mut Vec<RangeOverride>) -> I40F24 {
+    if needed <= I40F24::from_num(max) {
+        whole(rng, (needed.ceil().to_num::<u32>().max(min), max))
+    } else {
+        overrides.push(RangeOverride { satellite, function, max, needed });
+        needed
+    }
+}
+
+//Takes the images one at a time, switching instruments and calibrating as needed, and returns
+//the plan with the fuel and capacity it uses on each satellite.
+fn witness_plan(state: &SatelliteState, goals: &SatelliteGoals) -> (Vec<SatelliteOperator<SatelliteEnum>>, BTreeMap<SatelliteEnum, I40F24>, BTreeMap<SatelliteEnum, I40F24>) {
+    let zero = I40F24::from_num(0);
+    let mut plan = vec![];
+    let mut pointing = state.pointing.clone();
+    let mut powered: BTreeMap<SatelliteEnum, SatelliteEnum> = BTreeMap::new();
+    let mut fuel_needed: BTreeMap<SatelliteEnum, I40F24> = BTreeMap::new();
+    let mut capacity_needed: BTreeMap<SatelliteEnum, I40F24> = BTreeMap::new();
+    for (direction, mode) in goals.have_image.iter() {
+        let (satellite, instrument) = state.onboard.iter()
+            .flat_map(|(satellite, instruments)| instruments.iter().map(move |instrument| (*satellite, *instrument)))
+            .find(|(_, instrument)| state.does_instrument_support_mode(instrument, mode))
+            .unwrap();
+        let mut turn = |to: SatelliteEnum, plan: &mut Vec<SatelliteOperator<SatelliteEnum>>| {
+            let from = pointing[&satellite];
+            if from != to {
+                plan.push(TurnTo(satellite, to, from));
+                *fuel_needed.entry(satellite).or_insert(zero) += state.slew_cost(&to, &from);
+                pointing.insert(satellite, to);
+            }
+        };
+        if powered.get(&satellite) != Some(&instrument) {
+            if let Some(other) = powered.insert(satellite, instrument) {
+                plan.push(SwitchOff(other, satellite));
+            }
+            plan.push(SwitchOn(instrument, satellite));
+            let target = state.calibration_target[&instrument];
+            turn(target, &mut plan);
+            plan.push(Calibrate(satellite, instrument, target));
+        }
+        turn(*direction, &mut plan);
+        plan.push(TakeImage(satellite, *direction, instrument, *mode));
+        *capacity_needed.entry(satellite).or_insert(zero) += state.satellite_data_stored.get(&(*direction, *mode)).copied().unwrap_or(zero);
+    }
+    (plan, fuel_needed, capacity_needed)
+}
+
+//Drawn as u64 so that the top of the range can be u32::MAX.
+fn whole(rng: &mut StdRng, (min, max): (u32, u32)) -> I40F24 {
+    I40F24::from_num(rng.gen_range(min as u64, max as u64 + 1))
+}
+
+//Read from the decimal text so that it prints back the same, e.g. 4.86 rather than 4.8599999.
+fn hundredths(rng: &mut StdRng, (min, max): (u32, u32)) -> I40F24 {
+    let n = rng.gen_range(min as u64 * 100, max as u64 * 100 + 1);
+    I40F24::from_str(format!("{}.{:02}", n / 100, n % 100).as_str()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pddl_parser::make_satellite_problem_from_str;
+    use crate::plan_io::{parse_plan, write_plan};
+    use crate::validator::validate_plan;
+
+    #[test]
+    fn generated_problems_are_reproducible_and_solvable() {
+        let config = GeneratorConfig { satellites: 3, max_instruments: 3, modes: 4, targets: 12, observations: 15, seed: 7, ..GeneratorConfig::default() };
+        let problem = generate(&config).unwrap();
+        assert_eq!(generate(&config).unwrap(), problem);
+        assert_ne!(generate(&GeneratorConfig { seed: 8, ..config.clone() }).unwrap(), problem);
+        assert_eq!(problem.goals.have_image.len(), 15);
+        assert!(validate_plan(problem.witness.as_ref().unwrap(), &problem.state, &problem.goals).is_valid());
+
+        //Read back, the objects may be numbered differently, so the witness goes by name.
+        let (state, goals, objects) = make_satellite_problem_from_str(problem.to_pddl("generated").as_str()).unwrap();
+        let mut witness = vec![];
+        write_plan(problem.witness.as_ref().unwrap(), &problem.objects, &mut witness).unwrap();
+        let read_witness = parse_plan(String::from_utf8(witness).unwrap().as_str(), &objects).unwrap();
+        assert!(validate_plan(&read_witness, &state, &goals).is_valid());
+        assert_eq!(objects.len(), problem.objects.len());
+
+        let strips = generate(&GeneratorConfig { semantics: Semantics::Strips, ..config.clone() }).unwrap();
+        assert!(strips.state.fuel.is_empty());
+        assert!(validate_plan(strips.witness.as_ref().unwrap(), &strips.state, &strips.goals).is_valid());
+    }
+
+    #[test]
+    fn impossible_configurations_are_errors() {
+        assert!(generate(&GeneratorConfig { targets: 1, ..GeneratorConfig::default() }).is_err());
+        assert!(generate(&GeneratorConfig { targets: 4, modes: 2, observations: 7, ..GeneratorConfig::default() }).is_err());
+        assert!(generate(&GeneratorConfig { fuel: (10, 5), ..GeneratorConfig::default() }).is_err());
+        assert!(generate(&GeneratorConfig { solvable: false, ..GeneratorConfig::default() }).unwrap().witness.is_none());
+    }
+
+    #[test]
+    fn witnesses_stay_within_the_ranges_when_they_can() {
+        let config = GeneratorConfig { satellites: 2, targets: 9, observations: 6, slew_time: (50, 60), seed: 3, ..GeneratorConfig::default() };
+        let roomy = generate(&GeneratorConfig { fuel: (1, 100_000), capacity: (1, 100_000), ..config.clone() }).unwrap();
+        assert!(roomy.overrides.is_empty());
+        assert!(roomy.state.fuel.values().all(|fuel| *fuel <= I40F24::from_num(100_000)));
+        assert!(validate_plan(roomy.witness.as_ref().unwrap(), &roomy.state, &roomy.goals).is_valid());
+
+        let cramped = generate(&GeneratorConfig { fuel: (1, 1), capacity: (100_000, 100_000), ..config.clone() }).unwrap();
+        assert!(!cramped.overrides.is_empty());
+        for raised in cramped.overrides.iter() {
+            assert_eq!(raised.function, "fuel");
+            assert_eq!(cramped.state.fuel[&raised.satellite], raised.needed);
+        }
+        assert!(validate_plan(cramped.witness.as_ref().unwrap(), &cramped.state, &cramped.goals).is_valid());
+    }
+
+    #[test]
+    fn ranges_may_reach_the_largest_u32() {
+        let top = (u32::MAX, u32::MAX);
+        let problem = generate(&GeneratorConfig { fuel: top, capacity: top, image_size: (1, 1), slew_time: top, solvable: false, ..GeneratorConfig::default() }).unwrap();
+        assert!(problem.state.fuel.values().all(|fuel| *fuel == I40F24::from_num(u32::MAX)));
+        assert!(problem.state.slew_time.values().all(|time| *time == I40F24::from_num(u32::MAX)));
+    }
+}
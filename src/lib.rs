@@ -9,9 +9,22 @@ extern crate strum_macros;
 extern crate fixed;
 extern crate log;
 
+pub mod benchmark;
+pub mod domain;
+pub mod generator;
 pub mod methods;
+pub mod metric;
+pub mod object_table;
 pub mod operators;
+pub mod parse_error;
 pub mod pddl_parser;
+pub mod pddl_writer;
+pub mod plan_io;
+#[cfg(feature = "serde")]
+pub mod serialization;
+mod sexpr;
+pub mod simulator;
+pub mod validator;
 
 
 #[cfg(test)]
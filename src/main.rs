@@ -0,0 +1,375 @@
+use std::{env, fs, io, process};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhop::AnytimePlannerBuilder;
+use fixed::types::I40F24;
+use log::{info, warn, LevelFilter};
+
+use satellite_numeric::operators::{apply_plan, plan_cost, GoalOrdering, Heuristic, OperatorCosts, SatelliteEnum, SatelliteGoals, SatelliteOperator, SatelliteState, Semantics};
+use satellite_numeric::pddl_parser::make_satellite_problem_from;
+use satellite_numeric::plan_io::{read_plan, write_plan};
+use satellite_numeric::benchmark::{run_benchmarks, write_csv, PlannerSetup};
+use satellite_numeric::domain::{check_problem, domain_text};
+use satellite_numeric::generator::{generate, GeneratorConfig};
+use satellite_numeric::object_table::ObjectTable;
+use satellite_numeric::parse_error::SatelliteParseError;
+use satellite_numeric::simulator::{simulate, trace_to_json, write_trace_table};
+use satellite_numeric::validator::validate_plan;
+
+const USAGE: &str = "Usage: satellite_numeric check <problem.pddl>\n       satellite_numeric bench <problem directory> [-t <time limit>,...] [--setup <heuristic>[:<orderings>[:<branching limit>]]]... [-o <csv file>]\n       satellite_numeric generate [--satellites <n>] [--instruments <max per satellite>] [--modes <n>] [--targets <n>] [--observations <n>] [--fuel <min-max>] [--capacity <min-max>] [--image-size <min-max>] [--slew <min-max>] [--seed <n>] [strips] [--unsolvable] [-o <problem file>]\n       satellite_numeric domain [strips|numeric]\n       satellite_numeric validate <problem.pddl> <plan file> [strips]\n       satellite_numeric simulate <problem.pddl> <plan file> [strips] [--json]\n       satellite_numeric <problem.pddl> [-t <time limit, e.g. 5s or 500ms>] [strips] [-v <verbosity>] [-o <plan file>] [-c <action>=<cost>]... [-b <branching limit>] [--order <nearest,instrument,satellite,key>] [--heuristic <admissible|fast|count>]\nPlans are printed once the time limit is up, in the order they were found.";
+
+const DEFAULT_TIME_LIMIT_MS: u128 = 5000;
+
+struct PlanArgs {
+    problem: String,
+    time_limit_ms: u128,
+    strips: bool,
+    verbosity: usize,
+    output: Option<String>,
+    costs: OperatorCosts,
+    orderings: Option<Vec<GoalOrdering>>,
+    branching_limit: Option<usize>,
+    heuristic: Option<Heuristic>,
+}
+
+impl PlanArgs {
+    fn from(args: &[String]) -> Result<PlanArgs, String> {
+        let mut problem = None;
+        let mut time_limit_ms = DEFAULT_TIME_LIMIT_MS;
+        let mut strips = false;
+        let mut verbosity = 0;
+        let mut output = None;
+        let mut costs = OperatorCosts::default();
+        let mut orderings = None;
+        let mut branching_limit = None;
+        let mut heuristic = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-t" | "--time" => time_limit_ms = parse_time_limit(next_value(&mut args, arg)?)?,
+                "-v" | "--verbose" => verbosity = next_value(&mut args, arg)?.parse().map_err(|_| String::from("The verbosity must be a number"))?,
+                "-o" | "--output" => output = Some(String::from(next_value(&mut args, arg)?)),
+                "-c" | "--cost" => parse_cost(next_value(&mut args, arg)?, &mut costs)?,
+                "-b" | "--branching" => branching_limit = Some(next_value(&mut args, arg)?.parse::<usize>().ok().filter(|limit| *limit > 0)
+                    .ok_or_else(|| String::from("The branching limit must be a number above 0"))?),
+                "--order" => orderings = Some(parse_orderings(next_value(&mut args, arg)?)?),
+                "--heuristic" => heuristic = Some(next_value(&mut args, arg).and_then(|name| Heuristic::from_str(name).map_err(|_| format!("Unknown heuristic {}", name)))?),
+                "strips" | "--strips" => strips = true,
+                "numeric" | "--numeric" => strips = false,
+                //The anyhop style of giving the time limit, e.g. -5s
+                time if time.starts_with('-') && time[1..].starts_with(|c: char| c.is_ascii_digit()) => time_limit_ms = parse_time_limit(&time[1..])?,
+                flag if flag.starts_with('-') => return Err(format!("Unknown option {}", flag)),
+                path => problem = Some(String::from(path)),
+            }
+        }
+        match problem {
+            Some(problem) => Ok(PlanArgs { problem, time_limit_ms, strips, verbosity, output, costs, orderings, branching_limit, heuristic }),
+            None => Err(String::from("No problem file given")),
+        }
+    }
+}
+
+fn next_value<'a, I: Iterator<Item=&'a String>>(args: &mut I, flag: &str) -> Result<&'a str, String> {
+    args.next().map(|value| value.as_str()).ok_or_else(|| format!("{} needs a value", flag))
+}
+
+//Accepts ms, s and m suffixes; a bare number is in seconds.
+fn parse_time_limit(text: &str) -> Result<u128, String> {
+    let (number, scale) = if text.ends_with("ms") {
+        (&text[..text.len() - 2], 1)
+    } else if text.ends_with('s') {
+        (&text[..text.len() - 1], 1000)
+    } else if text.ends_with('m') {
+        (&text[..text.len() - 1], 60000)
+    } else {
+        (text, 1000)
+    };
+    number.parse::<u128>().map(|n| n * scale).map_err(|_| format!("Bad time limit: {}", text))
+}
+
+//Reads a cost such as switch_on=2.5 into costs.
+fn parse_cost(text: &str, costs: &mut OperatorCosts) -> Result<(), String> {
+    let mut parts = text.splitn(2, '=');
+    let action = parts.next().unwrap_or("");
+    let cost = parts.next()
+        .and_then(|value| I40F24::from_str(value).ok())
+        .ok_or_else(|| format!("Bad cost: {}", text))?;
+    if costs.set(action, cost) {
+        Ok(())
+    } else {
+        Err(format!("No configurable cost for {}", action))
+    }
+}
+
+//Reads a comma-separated list of orderings such as nearest,instrument.
+fn parse_orderings(text: &str) -> Result<Vec<GoalOrdering>, String> {
+    text.split(',')
+        .map(|name| GoalOrdering::from_str(name.trim()).map_err(|_| format!("Unknown ordering {}", name)))
+        .collect()
+}
+
+fn setup_logger(verbosity: usize) -> Result<(), fern::InitError> {
+    let level = match verbosity {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    fern::Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!("{}[{}][{}] {}",
+                                    chrono::Local::now().format("[%Y-%m-%d][%H:%M:%S]"),
+                                    record.target(),
+                                    record.level(),
+                                    message))
+        })
+        .level(level)
+        .chain(io::stderr())
+        .apply()?;
+    Ok(())
+}
+
+fn run_plan(args: &PlanArgs) -> io::Result<()> {
+    //The check is advisory: if it cannot read the problem, the parser below reports why.
+    match fs::read_to_string(args.problem.as_str()).map_err(SatelliteParseError::from).and_then(|contents| check_problem(contents.as_str())) {
+        Ok(issues) => {
+            for issue in issues {
+                warn!("{}", issue);
+            }
+        }
+        Err(e) => warn!("Could not check {} against the domain: {}", args.problem, e),
+    }
+    let (mut start, goal, objects) = make_satellite_problem_from(args.problem.as_str())?;
+    if args.strips {
+        start.set_semantics(Semantics::Strips);
+    }
+    start.config.costs = args.costs;
+    if let Some(orderings) = args.orderings.as_ref() {
+        start.config.orderings = orderings.clone();
+    }
+    start.config.branching_limit = args.branching_limit;
+    if let Some(heuristic) = args.heuristic {
+        start.config.heuristic = heuristic;
+    }
+    info!("Planning for {} ({:?}) with a limit of {}ms", args.problem, start.config.semantics, args.time_limit_ms);
+
+    let outcome = AnytimePlannerBuilder::state_goal(&start, &goal)
+        .time_limit_ms(args.time_limit_ms)
+        .verbose(args.verbosity)
+        .construct();
+
+    //anyhop only hands plans back once construct() returns, so they are printed after the search,
+    //in the order they were found and with when they were found.
+    let mut best: Option<(I40F24, Vec<SatelliteOperator<SatelliteEnum>>)> = None;
+    for (i, plan) in outcome.get_all_plans().into_iter().enumerate() {
+        match plan_cost(&plan, &start, &goal) {
+            Some(cost) => {
+                match apply_plan(&plan, &start).and_then(|end| goal.metric_value(&end)) {
+                    Some(metric) => println!("Plan {} (found after {}ms, cost {}, metric {}, {} steps):", i + 1, outcome.get_discovery_time(i), cost, metric, plan.len()),
+                    None => println!("Plan {} (found after {}ms, cost {}, {} steps):", i + 1, outcome.get_discovery_time(i), cost, plan.len()),
+                }
+                print!("{}", objects.named(&plan));
+                if best.as_ref().map_or(true, |(best_cost, _)| cost < *best_cost) {
+                    best = Some((cost, plan));
+                }
+            }
+            None => println!("Plan {} could not be applied to the initial state", i + 1),
+        }
+    }
+
+    match (best, args.output.as_ref()) {
+        (Some((_, plan)), Some(output)) => write_plan(&plan, &objects, &mut fs::File::create(output)?),
+        (None, _) => {
+            println!("No plan found within {}ms", args.time_limit_ms);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+//Lists whatever in the problem does not match the domain. Returns whether there was nothing to report.
+fn run_check(args: &[String]) -> io::Result<bool> {
+    if args.len() != 1 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("check needs a problem\n{}", USAGE)));
+    }
+    let issues = check_problem(fs::read_to_string(args[0].as_str())?.as_str())?;
+    for issue in issues.iter() {
+        println!("{}", issue);
+    }
+    Ok(issues.is_empty())
+}
+
+//Prints the bundled domain file, numeric unless strips is given.
+fn run_domain(args: &[String]) -> io::Result<bool> {
+    let semantics = match args.first().map(|arg| arg.as_str()) {
+        None | Some("numeric") => Semantics::Numeric,
+        Some("strips") => Semantics::Strips,
+        Some(other) => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown domain {}\n{}", other, USAGE))),
+    };
+    print!("{}", domain_text(semantics));
+    Ok(true)
+}
+
+//Loads the problem and plan named in the arguments of validate and simulate, skipping the flags.
+fn load_problem_and_plan(args: &[String], command: &str) -> io::Result<(SatelliteState, SatelliteGoals, ObjectTable, Vec<SatelliteOperator<SatelliteEnum>>)> {
+    let strips = args.iter().any(|arg| arg == "strips" || arg == "--strips");
+    let files: Vec<&String> = args.iter().filter(|arg| !arg.starts_with('-') && *arg != "strips").collect();
+    if files.len() != 2 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} needs a problem and a plan\n{}", command, USAGE)));
+    }
+    let (mut start, goal, objects) = make_satellite_problem_from(files[0].as_str())?;
+    if strips {
+        start.set_semantics(Semantics::Strips);
+    }
+    let plan = read_plan(fs::File::open(files[1])?, &objects)?;
+    Ok((start, goal, objects, plan))
+}
+
+//Checks a plan file against a problem, printing why it fails if it does. Returns whether it is valid.
+fn run_validate(args: &[String]) -> io::Result<bool> {
+    let (start, goal, objects, plan) = load_problem_and_plan(args, "validate")?;
+    let report = validate_plan(&plan, &start, &goal);
+    print!("{}", objects.named(&report));
+    Ok(report.is_valid())
+}
+
+//Prints what each step of a plan file changes, as a table or with --json as JSON. Returns whether
+//every step could be applied.
+fn run_simulate(args: &[String]) -> io::Result<bool> {
+    let (start, _, objects, plan) = load_problem_and_plan(args, "simulate")?;
+    let trace = simulate(&plan, &start);
+    if args.iter().any(|arg| arg == "--json") {
+        print!("{}", trace_to_json(&trace, &objects));
+    } else {
+        write_trace_table(&trace, &objects, &mut io::stdout())?;
+    }
+    Ok(trace.completed())
+}
+
+//Reads a range such as 100-250; a single number is a range of one.
+fn parse_range(text: &str) -> Result<(u32, u32), String> {
+    let bad_range = || format!("Bad range: {}", text);
+    let mut parts = text.splitn(2, '-');
+    let min = parts.next().unwrap_or("").parse().map_err(|_| bad_range())?;
+    let max = match parts.next() {
+        Some(max) => max.parse().map_err(|_| bad_range())?,
+        None => min,
+    };
+    Ok((min, max))
+}
+
+fn generator_config(args: &[String]) -> Result<(GeneratorConfig, Option<String>), String> {
+    let mut config = GeneratorConfig::default();
+    let mut output = None;
+    let count = |value: &str, flag: &str| value.parse::<usize>().map_err(|_| format!("{} must be a number", flag));
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--satellites" => config.satellites = count(next_value(&mut args, arg)?, arg)?,
+            "--instruments" => config.max_instruments = count(next_value(&mut args, arg)?, arg)?,
+            "--modes" => config.modes = count(next_value(&mut args, arg)?, arg)?,
+            "--targets" => config.targets = count(next_value(&mut args, arg)?, arg)?,
+            "--observations" => config.observations = count(next_value(&mut args, arg)?, arg)?,
+            "--fuel" => config.fuel = parse_range(next_value(&mut args, arg)?)?,
+            "--capacity" => config.capacity = parse_range(next_value(&mut args, arg)?)?,
+            "--image-size" => config.image_size = parse_range(next_value(&mut args, arg)?)?,
+            "--slew" => config.slew_time = parse_range(next_value(&mut args, arg)?)?,
+            "--seed" => config.seed = next_value(&mut args, arg)?.parse().map_err(|_| String::from("The seed must be a number"))?,
+            "-o" | "--output" => output = Some(String::from(next_value(&mut args, arg)?)),
+            "strips" | "--strips" => config.semantics = Semantics::Strips,
+            "numeric" | "--numeric" => config.semantics = Semantics::Numeric,
+            "--unsolvable" => config.solvable = false,
+            other => return Err(format!("Unknown option {}", other)),
+        }
+    }
+    Ok((config, output))
+}
+
+//Writes a random problem to the output file, or prints it.
+fn run_generate(args: &[String]) -> io::Result<bool> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, format!("{}\n{}", msg, USAGE));
+    let (config, output) = generator_config(args).map_err(invalid)?;
+    let problem = generate(&config).map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+    let pddl = problem.to_pddl(format!("satgen-{}-{}", config.semantics, config.seed).as_str());
+    match output {
+        Some(output) => fs::write(output, pddl)?,
+        None => print!("{}", pddl),
+    }
+    Ok(true)
+}
+
+//Plans for every problem in a directory with each setup and time limit, writing a CSV of the
+//results. Returns whether every plan found was valid.
+fn run_bench(args: &[String]) -> io::Result<bool> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, format!("{}\n{}", msg, USAGE));
+    let mut directory = None;
+    let mut time_limits_ms = vec![];
+    let mut setups = vec![];
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-t" | "--time" => {
+                for limit in next_value(&mut args, arg).map_err(invalid)?.split(',') {
+                    time_limits_ms.push(parse_time_limit(limit).map_err(invalid)?);
+                }
+            }
+            "--setup" => setups.push(next_value(&mut args, arg).and_then(PlannerSetup::parse).map_err(invalid)?),
+            "-o" | "--output" => output = Some(next_value(&mut args, arg).map_err(invalid)?),
+            flag if flag.starts_with('-') => return Err(invalid(format!("Unknown option {}", flag))),
+            path => directory = Some(path),
+        }
+    }
+    let directory = directory.ok_or_else(|| invalid(String::from("bench needs a directory of problems")))?;
+    if time_limits_ms.is_empty() {
+        time_limits_ms.push(DEFAULT_TIME_LIMIT_MS);
+    }
+    if setups.is_empty() {
+        setups.push(PlannerSetup::default());
+    }
+    let results = run_benchmarks(Path::new(directory), &time_limits_ms, &setups)?;
+    match output {
+        Some(output) => write_csv(&results, &mut fs::File::create(output)?)?,
+        None => write_csv(&results, &mut io::stdout())?,
+    }
+    Ok(results.iter().all(|result| result.valid != Some(false)))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let subcommand: Option<fn(&[String]) -> io::Result<bool>> = match args.first().map(|arg| arg.as_str()) {
+        Some("bench") => Some(run_bench),
+        Some("check") => Some(run_check),
+        Some("domain") => Some(run_domain),
+        Some("generate") => Some(run_generate),
+        Some("validate") => Some(run_validate),
+        Some("simulate") => Some(run_simulate),
+        _ => None,
+    };
+    if let Some(subcommand) = subcommand {
+        setup_logger(0).unwrap_or_else(|e| eprintln!("Could not set up logging: {}", e));
+        match subcommand(&args[1..]) {
+            Ok(true) => return,
+            Ok(false) => process::exit(1),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        }
+    }
+    let plan_args = match PlanArgs::from(&args) {
+        Ok(plan_args) => plan_args,
+        Err(msg) => {
+            eprintln!("{}\n{}", msg, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = setup_logger(plan_args.verbosity) {
+        eprintln!("Could not set up logging: {}", e);
+    }
+    if let Err(e) = run_plan(&plan_args) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
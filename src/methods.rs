@@ -42,70 +42,55 @@ impl SatelliteStatus {
 }
 //Checks to see if the planner is done by comparing the state and the goal.
 pub fn is_satellite_done(state: SatelliteState, goal: &SatelliteGoals) -> bool {
-    for goal_image in goal.have_image.keys() {
-        if !state.have_image.contains_key(goal_image) {
+    for goal_image in goal.have_image.iter() {
+        if !state.have_image.contains(goal_image) {
             return false;
-        } else {
-            if !(state.have_image.get(goal_image) == goal.have_image.get(goal_image)) {
-                return false;
-            }
         }
     }
     return true;
 }
 
-//Turn an instrument on or off.
+//Turn an instrument on, first switching off whichever instrument on the same satellite is using its power.
+//Instruments on other satellites are left alone.
 fn switching(state: &SatelliteState, satellite: SatelliteEnum, instrument: SatelliteEnum) -> MethodResult<SatelliteOperator<SatelliteEnum>, SatelliteMethod> {
-    TaskLists(vec![if !state.power_on.is_empty() && !state.power_on.contains(&instrument) {
+    TaskLists(vec![if state.power_on.contains(&instrument) {
+        vec![]
+    } else {
         let powered_on_instrument = find_powered_on_instruments(state, &satellite);
         debug!("Our powered_on instrument is: {:?}", powered_on_instrument);
         match powered_on_instrument{
-            Some(n) => debug!("Performing switchoff on SwitchOff({:?}, {:?})",n, satellite ),
-            None => debug!("None"),
-
-        }
-        match powered_on_instrument{
-
-            Some(n)=> vec![Operator(SwitchOff(n, satellite)),
-                     Operator(SwitchOn(instrument, satellite))],
-
-
-            None =>
-                {
-                    if state.power_on.contains(&instrument){
-                        vec![Operator(SwitchOff(instrument, satellite)),
-                             Operator(SwitchOn(instrument, satellite))]
-                    }else{
-                        vec![Operator(SwitchOn(instrument, satellite))]
-                    }
-
-                }
+            Some(n)=> {
+                debug!("Performing switchoff on SwitchOff({:?}, {:?})",n, satellite );
+                vec![Operator(SwitchOff(n, satellite)),
+                     Operator(SwitchOn(instrument, satellite))]
+            },
+            None => vec![Operator(SwitchOn(instrument, satellite))],
         }
-    } else if state.power_on.is_empty() {
-        vec![Operator(SwitchOn(instrument, satellite))]
-    } else {
-        vec![]
     }])
 
 }
 //Schedules one step into the planner.
 fn schedule_one(state: &SatelliteState, satellite: SatelliteEnum, instrument: SatelliteEnum, mode: SatelliteEnum, new_direction: SatelliteEnum, previous_direction: SatelliteEnum) -> MethodResult<SatelliteOperator<SatelliteEnum>, SatelliteMethod> {
-    let is_instrument_powered_on = !state.power_avail.get(&satellite).unwrap();
+    //Only this satellite's instruments matter: power is not shared between satellites.
+    let is_other_instrument_powered_on = match find_powered_on_instruments(state, &satellite) {
+        Some(powered_on_instrument) => powered_on_instrument != instrument,
+        None => false,
+    };
 
     if is_satellite_pointing_in_direction(state, &satellite, &new_direction){ //Prevents short circuiting of the and from earlier
-         if is_instrument_powered_on || state.power_on.is_empty(){
+         if !is_other_instrument_powered_on {
              debug!("Scheduling pointing with on instruments");
-            return schedule_pointing_with_powered_on_instruments(satellite, instrument, mode, new_direction)
+            return schedule_pointing_with_powered_on_instruments(state, satellite, instrument, mode, new_direction)
         } else {
              debug!("Scheduling pointing with off instruments");
              return schedule_pointing_with_powered_off_instruments(state, &satellite, instrument, mode, new_direction)
 
         }
     }else{
-        if is_instrument_powered_on || state.power_on.is_empty(){
+        if !is_other_instrument_powered_on {
             debug!("Scheduling no pointing with on instruments");
             let calibration_target_direction = state.calibration_target.get(&instrument).unwrap();
-            return schedule_not_pointing_with_powered_on_instruments(satellite, instrument, mode, new_direction, previous_direction, calibration_target_direction)
+            return schedule_not_pointing_with_powered_on_instruments(state, satellite, instrument, mode, new_direction, previous_direction, calibration_target_direction)
         }else{
             debug!("Scheduling no pointing with off instruments");
             let calibration_target_direction = state.calibration_target.get(&instrument).unwrap();
@@ -173,8 +158,11 @@ fn schedule_not_pointing_with_powered_off_instruments(state: &SatelliteState, sa
     }
 }
 
-fn schedule_not_pointing_with_powered_on_instruments(satellite: SatelliteEnum, instrument: SatelliteEnum, mode: SatelliteEnum, new_direction: SatelliteEnum, previous_direction: SatelliteEnum, calibration_target_direction: &SatelliteEnum) -> MethodResult<SatelliteOperator<SatelliteEnum>, SatelliteMethod> {
-    if calibration_target_direction == &previous_direction && &new_direction==calibration_target_direction{
+fn schedule_not_pointing_with_powered_on_instruments(state: &SatelliteState, satellite: SatelliteEnum, instrument: SatelliteEnum, mode: SatelliteEnum, new_direction: SatelliteEnum, previous_direction: SatelliteEnum, calibration_target_direction: &SatelliteEnum) -> MethodResult<SatelliteOperator<SatelliteEnum>, SatelliteMethod> {
+    if is_ready_to_image(state, &instrument) {
+        TaskLists(vec![vec![Operator(TurnTo(satellite, new_direction, previous_direction)),
+                            Operator(TakeImage(satellite, new_direction, instrument, mode))]])
+    }else if calibration_target_direction == &previous_direction && &new_direction==calibration_target_direction{
         TaskLists(vec![vec![Method(Switching(satellite, instrument)),
                             Operator(Calibrate(satellite, instrument, *calibration_target_direction)),
                             Operator(TakeImage(satellite, new_direction, instrument, mode))]])
@@ -200,22 +188,40 @@ fn schedule_not_pointing_with_powered_on_instruments(satellite: SatelliteEnum, i
     }
 }
 
-fn schedule_pointing_with_powered_on_instruments(satellite: SatelliteEnum, instrument: SatelliteEnum, mode: SatelliteEnum, new_direction: SatelliteEnum) -> MethodResult<SatelliteOperator<SatelliteEnum>, SatelliteMethod> {
-    TaskLists(vec![vec![Method(Switching(satellite, instrument)),
-                        Operator(Calibrate(satellite, instrument, new_direction)),
-                        Operator(TakeImage(satellite, new_direction, instrument, mode))]])
+fn schedule_pointing_with_powered_on_instruments(state: &SatelliteState, satellite: SatelliteEnum, instrument: SatelliteEnum, mode: SatelliteEnum, new_direction: SatelliteEnum) -> MethodResult<SatelliteOperator<SatelliteEnum>, SatelliteMethod> {
+    if is_ready_to_image(state, &instrument) {
+        TaskLists(vec![vec![Operator(TakeImage(satellite, new_direction, instrument, mode))]])
+    } else {
+        let mut tasks = vec![Method(Switching(satellite, instrument))];
+        tasks.append(&mut calibrate_and_turn_back(state, satellite, instrument, new_direction));
+        tasks.push(Operator(TakeImage(satellite, new_direction, instrument, mode)));
+        TaskLists(vec![tasks])
+    }
 }
 
 fn schedule_pointing_with_powered_off_instruments(state: &SatelliteState, satellite: &SatelliteEnum, instrument: SatelliteEnum, mode: SatelliteEnum, new_direction: SatelliteEnum) -> MethodResult<SatelliteOperator<SatelliteEnum>, SatelliteMethod> {
-    match find_powered_on_instruments(state, &satellite) {
-        Some(instrument_to_power_off) => TaskLists(vec![vec![Operator(SwitchOff(instrument_to_power_off, *satellite)),
-                                                             Method(Switching(*satellite, instrument)),
-                                                             Operator(Calibrate(*satellite, instrument, new_direction)),
-                                                             Operator(TakeImage(*satellite, new_direction, instrument, mode))]]),
-        None => TaskLists(vec![vec![Method(Switching(*satellite, instrument)),
-                                    Operator(Calibrate(*satellite, instrument, new_direction)),
-                                    Operator(TakeImage(*satellite, new_direction, instrument, mode))]])
+    let mut tasks = vec![];
+    if let Some(instrument_to_power_off) = find_powered_on_instruments(state, &satellite) {
+        tasks.push(Operator(SwitchOff(instrument_to_power_off, *satellite)));
     }
+    tasks.push(Method(Switching(*satellite, instrument)));
+    tasks.append(&mut calibrate_and_turn_back(state, *satellite, instrument, new_direction));
+    tasks.push(Operator(TakeImage(*satellite, new_direction, instrument, mode)));
+    TaskLists(vec![tasks])
+}
+
+//Calibrates an instrument of a satellite pointing at direction, leaving it pointing there again.
+//The turns are dropped when direction is the calibration target.
+fn calibrate_and_turn_back(state: &SatelliteState, satellite: SatelliteEnum, instrument: SatelliteEnum, direction: SatelliteEnum) -> Vec<Task<SatelliteOperator<SatelliteEnum>, SatelliteMethod>> {
+    let calibration_target_direction = *state.calibration_target.get(&instrument).unwrap();
+    remove_redundant_turns(vec![Operator(TurnTo(satellite, calibration_target_direction, direction)),
+                                Operator(Calibrate(satellite, instrument, calibration_target_direction)),
+                                Operator(TurnTo(satellite, direction, calibration_target_direction))])
+}
+
+//Switching an instrument on clears its calibration, so only one that is on already can skip it.
+fn is_ready_to_image(state: &SatelliteState, instrument: &SatelliteEnum) -> bool {
+    state.power_on.contains(instrument) && state.calibrated.contains(instrument)
 }
 
 //Given a state, and a Satellite::SatelliteEnum, return an Instrument::Maybe<SatelliteEnum> containing any powered on instruments owned by the satellite.
@@ -242,25 +248,28 @@ fn is_satellite_pointing_in_direction(state: &SatelliteState, satellite: &Satell
 }
 
 fn schedule_all(state: &SatelliteState, goal: &SatelliteGoals) -> MethodResult<SatelliteOperator<SatelliteEnum>, SatelliteMethod> {
-    let mut tasks: Vec<Vec<Task<SatelliteOperator<SatelliteEnum>, SatelliteMethod>>> = vec![];
-    let mut completed_tasks: Vec<SatelliteEnum> = vec![];
-    for goal_image in goal.have_image.keys() {
-        if !(state.have_image.get(goal_image) == goal.have_image.get(goal_image)) {
-            let goal_image_clone = goal_image.clone();
-            let mode = goal.have_image.get(goal_image).unwrap();
-            let instrument = brute_force_instrument(state, mode).unwrap(); //First look up the goal image to see which mode it should be in, and then look up which mode it should be in.
-            let new_direction = goal_image_clone;
-            let satellite = brute_force_satellite(state, &instrument, mode).unwrap();
-            let previous_direction = state.pointing.get(&satellite.clone()).unwrap();
-            tasks.push(vec![Task::Method(ScheduleOne(satellite, instrument, mode.clone(), new_direction, previous_direction.clone())), Task::Method(ScheduleAll)]);
+    let mut candidates: Vec<Candidate> = vec![];
+    let mut completed_tasks: Vec<(SatelliteEnum, SatelliteEnum)> = vec![];
+    for goal_image in goal.have_image.iter() {
+        if !state.have_image.contains(goal_image) {
+            let (new_direction, mode) = goal_image;
+            //Every satellite and instrument that could take the image is an alternative for the planner to explore.
+            for (satellite, instrument) in capable_pairs(state, mode) {
+                let previous_direction = state.pointing.get(&satellite).unwrap();
+                candidates.push((satellite, instrument, mode.clone(), new_direction.clone(), previous_direction.clone()));
+            }
         } else {
             let image_clone = goal_image.clone();
             completed_tasks.push(image_clone);
         }
     }
-    return if goal.have_image.keys().eq(&completed_tasks) {
+    let tasks: Vec<Vec<Task<SatelliteOperator<SatelliteEnum>, SatelliteMethod>>> = order_candidates(state, candidates).into_iter()
+        .map(|(satellite, instrument, mode, new_direction, previous_direction)| vec![Task::Method(ScheduleOne(satellite, instrument, mode, new_direction, previous_direction)), Task::Method(ScheduleAll)])
+        .collect();
+    return if goal.have_image.iter().eq(&completed_tasks) {
+        //A satellite that is not pointing anywhere cannot turn, so its goal stays unmet.
         let pointing_tasks = pointing_needed(state, goal).iter()
-            .map(|(sat, dir)| Task::Operator(TurnTo(*sat, *dir, *state.pointing.get(sat).unwrap())))
+            .filter_map(|(sat, dir)| state.pointing.get(sat).map(|previous| Task::Operator(TurnTo(*sat, *dir, *previous))))
             .collect();
         TaskLists(vec![pointing_tasks])
     }else if tasks.len()>0{
@@ -268,7 +277,7 @@ fn schedule_all(state: &SatelliteState, goal: &SatelliteGoals) -> MethodResult<S
     }else {
         debug!("ScheduleAll is returning failure");
         debug!("completed_tasks: {:?}", completed_tasks);
-        debug!("goals:           {:?}", goal.have_image.keys());
+        debug!("goals:           {:?}", goal.have_image);
         debug!("goal.pointing:   {:?}", goal.pointing);
         for satellite in state.pointing.iter() {
             debug!("pointing: {:?}", satellite);
@@ -279,35 +288,79 @@ fn schedule_all(state: &SatelliteState, goal: &SatelliteGoals) -> MethodResult<S
 fn pointing_needed(state: &SatelliteState, goal: &SatelliteGoals) -> Vec<(SatelliteEnum,SatelliteEnum)> {
     goal.pointing.iter()
         .map(|(sat, dir)| (*sat, *dir))
-        .filter(|(sat, dir)| dir != state.pointing.get(&sat).unwrap())
+        .filter(|(sat, dir)| state.pointing.get(sat) != Some(dir))
         .collect()
 }
 
-//This method returns a Maybe<Instrument> from a state, and a mode.
-fn brute_force_instrument(state: &SatelliteState, mode: &SatelliteEnum)  -> Option<SatelliteEnum> {
-    for instrument in state.supports.keys(){
-        if state.supports.get(instrument)?.contains(mode){
-            return Some(instrument.clone());
+//satellite, instrument, mode, new_direction, previous_direction: the arguments of a ScheduleOne.
+type Candidate = (SatelliteEnum, SatelliteEnum, SatelliteEnum, SatelliteEnum, SatelliteEnum);
+
+//Ranks the candidates by each of the state's orderings and takes the best remaining candidate
+//from each ordering in turn, so that the first alternatives the planner explores are every
+//ordering's favourites. Stops at the branching limit, if there is one.
+fn order_candidates(state: &SatelliteState, candidates: Vec<Candidate>) -> Vec<Candidate> {
+    let rankings: Vec<Vec<Candidate>> = if state.config.orderings.is_empty() {
+        vec![candidates.clone()]
+    } else {
+        state.config.orderings.iter().map(|ordering| rank_candidates(state, &candidates, *ordering)).collect()
+    };
+    let limit = state.config.branching_limit.unwrap_or(candidates.len()).min(candidates.len());
+    let mut ordered: Vec<Candidate> = vec![];
+    for position in 0..candidates.len() {
+        for ranking in rankings.iter() {
+            if ordered.len() < limit && !ordered.contains(&ranking[position]) {
+                ordered.push(ranking[position]);
+            }
         }
     }
-    return None;
+    ordered
 }
 
-//This method returns a Satellite::Maybe<SatelliteEnum> from a state, an instrument and a mode.
-fn brute_force_satellite(state: &SatelliteState, instrument: &SatelliteEnum, mode: &SatelliteEnum) -> Option<SatelliteEnum> {
-    for satellites in state.onboard.keys() {
-        if state.does_instrument_support_mode(instrument, mode)&& is_onboard(state, satellites.clone(), instrument.clone()){
-            return Some(satellites.clone());
-        }
+fn rank_candidates(state: &SatelliteState, candidates: &[Candidate], ordering: GoalOrdering) -> Vec<Candidate> {
+    let mut ranked = candidates.to_vec();
+    //sort_by_key is stable, so ties stay in key order.
+    match ordering {
+        GoalOrdering::KeyOrder => {}
+        GoalOrdering::NearestSlew => ranked.sort_by_key(|candidate| estimated_turning(state, candidate)),
+        GoalOrdering::ByInstrument => ranked.sort_by_key(|candidate| {
+            let instrument = candidate.1;
+            (!state.power_on.contains(&instrument), !state.calibrated.contains(&instrument), instrument, estimated_turning(state, candidate))
+        }),
+        GoalOrdering::BySatellite => ranked.sort_by_key(|candidate| {
+            let satellite = candidate.0;
+            (find_powered_on_instruments(state, &satellite).is_none(), satellite, estimated_turning(state, candidate))
+        }),
     }
-    return None;
+    ranked
 }
 
-fn is_onboard(state: &SatelliteState, satellite: SatelliteEnum, instrument: SatelliteEnum) -> bool{
-    match state.onboard.get(&satellite){
-        Some(n) => n.contains(&instrument),
-        None => false,
+//The slew time ScheduleOne will spend: straight to the target if the instrument is on and
+//calibrated, otherwise by way of its calibration target.
+fn estimated_turning(state: &SatelliteState, candidate: &Candidate) -> I40F24 {
+    let (_, instrument, _, new_direction, previous_direction) = candidate;
+    if is_ready_to_image(state, instrument) {
+        state.slew_cost(new_direction, previous_direction)
+    } else {
+        let calibration_target = state.calibration_target.get(instrument).unwrap();
+        state.slew_cost(calibration_target, previous_direction) + state.slew_cost(new_direction, calibration_target)
+    }
+}
+
+//Every (satellite, instrument) pair that can take an image in the mode: the instrument is on board,
+//supports the mode and has a calibration target, and the satellite is pointing somewhere.
+fn capable_pairs(state: &SatelliteState, mode: &SatelliteEnum) -> Vec<(SatelliteEnum, SatelliteEnum)> {
+    let mut pairs = vec![];
+    for (satellite, instruments) in state.onboard.iter() {
+        if !state.pointing.contains_key(satellite) {
+            continue;
+        }
+        for instrument in instruments.iter() {
+            if state.does_instrument_support_mode(instrument, mode) && state.calibration_target.contains_key(instrument) {
+                pairs.push((*satellite, *instrument));
+            }
+        }
     }
+    pairs
 }
 
 impl Method for SatelliteMethod {
@@ -339,21 +392,358 @@ impl Goal for SatelliteGoals {
     }
 
     fn distance_from(&self, state: &Self::S) -> Self::C {
-        let mut unvisited = 0;
-        let mut num_turns = 0;
-        for goal_image in self.have_image.keys(){
-            //If we haven't visited the image, add it to the unvisited count.
-            if state.have_image.get(goal_image)!=self.have_image.get(goal_image){
-                unvisited+=1;
+        match state.config.heuristic {
+            Heuristic::GoalCount => goal_count(self, state),
+            Heuristic::Admissible => admissible_distance(self, state),
+            Heuristic::Fast => fast_distance(self, state),
+        }
+    }
+}
+
+fn goal_count(goal: &SatelliteGoals, state: &SatelliteState) -> I40F24 {
+    let mut unvisited = 0;
+    let mut num_turns = 0;
+    for goal_image in goal.have_image.iter(){
+        //If we haven't visited the image, add it to the unvisited count.
+        if !state.have_image.contains(goal_image){
+            unvisited+=1;
+        }
+    }
+    for goal_pointing in goal.pointing.keys(){
+        if state.pointing.get(goal_pointing)!=goal.pointing.get(goal_pointing){
+            num_turns+=1;
+        }
+    }
+
+    return I40F24::from_num(unvisited+num_turns);
+}
+
+//Any plan needs a take_image for every open image, and must at least complete whichever open goal
+//is dearest on its own. Turns are bounded by the cheapest slew into the direction, so this stays a
+//lower bound even when the slew times break the triangle inequality. Turns and image data are
+//charged at the metric's rates, as Operator::cost charges them.
+fn admissible_distance(goal: &SatelliteGoals, state: &SatelliteState) -> I40F24 {
+    let open_images = open_images(goal, state);
+    let mut dearest = I40F24::from_num(0);
+    for (direction, mode) in open_images.iter() {
+        if let Some(cost) = image_cost(goal, state, direction, mode, true) {
+            dearest = dearest.max(cost);
+        }
+    }
+    for (satellite, direction) in pointing_needed(state, goal) {
+        if state.pointing.contains_key(&satellite) {
+            let (turn_rate, _) = metric_rates(goal, state, &satellite);
+            dearest = dearest.max(turn_rate.saturating_mul(cheapest_turn_to(state, &direction)));
+        }
+    }
+    return dearest + state.config.costs.take_image * I40F24::from_num(open_images.len());
+}
+
+//Every open goal's cheapest cost on its own, summed.
+fn fast_distance(goal: &SatelliteGoals, state: &SatelliteState) -> I40F24 {
+    let open_images = open_images(goal, state);
+    let mut total = state.config.costs.take_image * I40F24::from_num(open_images.len());
+    for (direction, mode) in open_images.iter() {
+        if let Some(cost) = image_cost(goal, state, direction, mode, false) {
+            total += cost;
+        }
+    }
+    for (satellite, direction) in pointing_needed(state, goal) {
+        if let Some(previous_direction) = state.pointing.get(&satellite) {
+            let (turn_rate, _) = metric_rates(goal, state, &satellite);
+            total += turn_rate.saturating_mul(state.slew_cost(&direction, previous_direction));
+        }
+    }
+    return total;
+}
+
+fn open_images(goal: &SatelliteGoals, state: &SatelliteState) -> Vec<(SatelliteEnum, SatelliteEnum)> {
+    goal.have_image.iter()
+        .filter(|image| !state.have_image.contains(*image))
+        .copied()
+        .collect()
+}
+
+//What each unit of slew time and of image data costs the satellite: the metric's rates, never below
+//zero, under Numeric semantics, and otherwise the slew time alone, as in Operator::cost.
+fn metric_rates(goal: &SatelliteGoals, state: &SatelliteState, satellite: &SatelliteEnum) -> (I40F24, I40F24) {
+    let zero = I40F24::from_num(0);
+    match &goal.metric {
+        Some(metric) if state.config.semantics == Semantics::Numeric => (metric.turn_rate(satellite).max(zero), metric.image_rate(satellite).max(zero)),
+        _ => (I40F24::from_num(1), zero),
+    }
+}
+
+//The cheapest way for any capable instrument to get ready for and point at the image, not counting
+//the take_image operator cost. None if no instrument can take it.
+fn image_cost(goal: &SatelliteGoals, state: &SatelliteState, direction: &SatelliteEnum, mode: &SatelliteEnum, admissible: bool) -> Option<I40F24> {
+    let costs = &state.config.costs;
+    let image_size = state.satellite_data_stored.get(&(*direction, *mode)).copied().unwrap_or(I40F24::from_num(0));
+    capable_pairs(state, mode).into_iter()
+        .map(|(satellite, instrument)| {
+            let (turn_rate, image_rate) = metric_rates(goal, state, &satellite);
+            let previous_direction = *state.pointing.get(&satellite).unwrap();
+            let powered = state.power_on.contains(&instrument);
+            let ready = powered && state.calibrated.contains(&instrument);
+            let mut cost = I40F24::from_num(0);
+            if !powered {
+                cost += costs.switch_on;
+                if find_powered_on_instruments(state, &satellite).is_some() {
+                    cost += costs.switch_off;
+                }
+            }
+            if !ready {
+                cost += costs.calibrate;
             }
+            let turning = if !admissible {
+                estimated_turning(state, &(satellite, instrument, *mode, *direction, previous_direction))
+            } else if ready {
+                cheapest_turn(state, direction, &previous_direction)
+            } else {
+                let calibration_target = state.calibration_target.get(&instrument).unwrap();
+                cheapest_turn(state, calibration_target, &previous_direction) + cheapest_turn(state, direction, calibration_target)
+            };
+            cost + turn_rate.saturating_mul(turning) + image_rate.saturating_mul(image_size)
+        })
+        .min()
+}
+
+//Nothing if already there, otherwise the cheapest slew into the direction from anywhere.
+fn cheapest_turn(state: &SatelliteState, direction: &SatelliteEnum, previous_direction: &SatelliteEnum) -> I40F24 {
+    if direction == previous_direction {
+        I40F24::from_num(0)
+    } else {
+        cheapest_turn_to(state, direction)
+    }
+}
+
+fn cheapest_turn_to(state: &SatelliteState, direction: &SatelliteEnum) -> I40F24 {
+    state.slew_time.iter()
+        .filter(|((new_direction, previous_direction), _)| new_direction == direction && previous_direction != direction)
+        .map(|(_, time)| *time)
+        .min()
+        .unwrap_or(I40F24::from_num(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhop::Operator as _;
+    use crate::pddl_parser::make_satellite_problem_from_str;
+
+    //satellite0 has instrument0 switched on; satellite1 has power for instrument1 or instrument2.
+    const TWO_SATELLITES: &str = "(define (problem two) (:domain satellite)
+        (:objects satellite0 - satellite satellite1 - satellite instrument0 - instrument instrument1 - instrument instrument2 - instrument
+                  image0 - mode star0 - direction star1 - direction star2 - direction)
+        (:init (on_board instrument0 satellite0) (on_board instrument1 satellite1) (on_board instrument2 satellite1)
+               (supports instrument0 image0) (supports instrument1 image0) (supports instrument2 image0)
+               (calibration_target instrument0 star0) (calibration_target instrument1 star1) (calibration_target instrument2 star1)
+               (power_on instrument0) (power_avail satellite1)
+               (pointing satellite0 star0) (pointing satellite1 star2))
+        (:goal (and (have_image star1 image0))))";
+
+    fn steps(result: MethodResult<SatelliteOperator<SatelliteEnum>, SatelliteMethod>) -> Vec<Vec<Result<SatelliteOperator<SatelliteEnum>, SatelliteMethod>>> {
+        match result {
+            TaskLists(lists) => lists.into_iter()
+                .map(|tasks| tasks.into_iter().map(|task| match task {
+                    Operator(op) => Ok(op),
+                    Method(method) => Err(method),
+                }).collect())
+                .collect(),
+            _ => panic!("Expected task lists"),
         }
-        for goal_pointing in self.pointing.keys(){
-            if state.pointing.get(goal_pointing)!=self.pointing.get(goal_pointing){
-                num_turns+=1;
+    }
+
+    #[test]
+    fn switching_ignores_other_satellites() {
+        let (state, goal, objects) = make_satellite_problem_from_str(TWO_SATELLITES).unwrap();
+        let satellite1 = objects.get("satellite1").unwrap();
+        let instrument1 = objects.get("instrument1").unwrap();
+        assert_eq!(steps(Switching(satellite1, instrument1).apply(&state, &goal)), vec![vec![Ok(SwitchOn(instrument1, satellite1))]]);
+    }
+
+    #[test]
+    fn switching_swaps_instruments_on_the_same_satellite() {
+        let (mut state, goal, objects) = make_satellite_problem_from_str(TWO_SATELLITES).unwrap();
+        let satellite1 = objects.get("satellite1").unwrap();
+        let instrument1 = objects.get("instrument1").unwrap();
+        let instrument2 = objects.get("instrument2").unwrap();
+        assert!(SwitchOn(instrument2, satellite1).attempt_update(&mut state));
+        assert_eq!(steps(Switching(satellite1, instrument1).apply(&state, &goal)),
+                   vec![vec![Ok(SwitchOff(instrument2, satellite1)), Ok(SwitchOn(instrument1, satellite1))]]);
+    }
+
+    #[test]
+    fn schedule_one_turns_to_the_calibration_target() {
+        let (state, goal, objects) = make_satellite_problem_from_str(TWO_SATELLITES).unwrap();
+        let satellite1 = objects.get("satellite1").unwrap();
+        let instrument1 = objects.get("instrument1").unwrap();
+        let image0 = objects.get("image0").unwrap();
+        let star1 = objects.get("star1").unwrap();
+        let star2 = objects.get("star2").unwrap();
+        //instrument0 being on must not make satellite1 skip the turn to star1.
+        assert_eq!(steps(ScheduleOne(satellite1, instrument1, image0, star1, star2).apply(&state, &goal)),
+                   vec![vec![Ok(TurnTo(satellite1, star1, star2)),
+                             Err(Switching(satellite1, instrument1)),
+                             Ok(Calibrate(satellite1, instrument1, star1)),
+                             Ok(TakeImage(satellite1, star1, instrument1, image0))]]);
+    }
+
+    #[test]
+    fn schedule_one_reuses_a_calibration() {
+        let (mut state, goal, objects) = make_satellite_problem_from_str(TWO_SATELLITES).unwrap();
+        let satellite0 = objects.get("satellite0").unwrap();
+        let instrument0 = objects.get("instrument0").unwrap();
+        let image0 = objects.get("image0").unwrap();
+        let star0 = objects.get("star0").unwrap();
+        let star1 = objects.get("star1").unwrap();
+        assert!(Calibrate(satellite0, instrument0, star0).attempt_update(&mut state));
+        assert_eq!(steps(ScheduleOne(satellite0, instrument0, image0, star1, star0).apply(&state, &goal)),
+                   vec![vec![Ok(TurnTo(satellite0, star1, star0)),
+                             Ok(TakeImage(satellite0, star1, instrument0, image0))]]);
+    }
+
+    #[test]
+    fn schedule_all_offers_every_capable_instrument() {
+        let (state, goal, objects) = make_satellite_problem_from_str(TWO_SATELLITES).unwrap();
+        let image0 = objects.get("image0").unwrap();
+        let star1 = objects.get("star1").unwrap();
+        let scheduled: Vec<SatelliteMethod> = steps(ScheduleAll.apply(&state, &goal)).into_iter()
+            .map(|tasks| match tasks[0] {
+                Err(method) => method,
+                Ok(op) => panic!("Expected ScheduleOne, got {:?}", op),
+            })
+            .collect();
+        let pair = |satellite: &str, instrument: &str, pointing: &str| ScheduleOne(objects.get(satellite).unwrap(), objects.get(instrument).unwrap(), image0, star1, objects.get(pointing).unwrap());
+        assert_eq!(scheduled, vec![pair("satellite0", "instrument0", "star0"),
+                                   pair("satellite1", "instrument1", "star2"),
+                                   pair("satellite1", "instrument2", "star2")]);
+    }
+
+    #[test]
+    fn schedule_all_follows_the_orderings_up_to_the_branching_limit() {
+        let (mut state, goal, objects) = make_satellite_problem_from_str(TWO_SATELLITES).unwrap();
+        let satellite1 = objects.get("satellite1").unwrap();
+        let instrument2 = objects.get("instrument2").unwrap();
+        assert!(SwitchOn(instrument2, satellite1).attempt_update(&mut state));
+        state.config.orderings = vec![GoalOrdering::ByInstrument];
+        state.config.branching_limit = Some(2);
+        let instruments: Vec<&str> = steps(ScheduleAll.apply(&state, &goal)).into_iter()
+            .map(|tasks| match tasks[0] {
+                Err(ScheduleOne(_, instrument, _, _, _)) => objects.name_of_enum(&instrument).unwrap(),
+                _ => panic!("Expected ScheduleOne, got {:?}", tasks[0]),
+            })
+            .collect();
+        assert_eq!(instruments, vec!["instrument0", "instrument2"]);
+    }
+
+    //Decomposes the tasks depth first, always taking the first alternative, and returns the plan.
+    fn first_plan(state: &mut SatelliteState, goal: &SatelliteGoals, tasks: Vec<Task<SatelliteOperator<SatelliteEnum>, SatelliteMethod>>) -> Vec<SatelliteOperator<SatelliteEnum>> {
+        let mut plan = vec![];
+        for task in tasks {
+            match task {
+                Operator(op) => {
+                    assert!(op.attempt_update(state), "{:?} failed after {:?}", op, plan);
+                    plan.push(op);
+                }
+                Method(method) => match method.apply(state, goal) {
+                    TaskLists(mut lists) => plan.append(&mut first_plan(state, goal, lists.remove(0))),
+                    _ => panic!("{:?} failed after {:?}", method, plan),
+                },
             }
         }
+        plan
+    }
 
-        return I40F24::from_num(unvisited+num_turns);
+    #[test]
+    fn one_direction_can_be_imaged_in_two_modes() {
+        let problem = "(define (problem two_modes) (:domain satellite)
+            (:objects satellite0 - satellite instrument0 - instrument image0 - mode image1 - mode star0 - direction star1 - direction)
+            (:init (on_board instrument0 satellite0) (supports instrument0 image0) (supports instrument0 image1)
+                   (calibration_target instrument0 star0) (power_avail satellite0) (pointing satellite0 star0))
+            (:goal (and (have_image star1 image0) (have_image star1 image1))))";
+        //Starting at star1, the instrument has to be calibrated at star0 before the first image.
+        for pointing in ["star0", "star1"].iter() {
+            let problem = problem.replace("(pointing satellite0 star0)", format!("(pointing satellite0 {})", pointing).as_str());
+            let (start, goal, objects) = make_satellite_problem_from_str(problem.as_str()).unwrap();
+            assert_eq!(steps(ScheduleAll.apply(&start, &goal)).len(), 2);
+
+            let plan = first_plan(&mut start.clone(), &goal, vec![Method(ScheduleAll)]);
+            let report = crate::validator::validate_plan(&plan, &start, &goal);
+            assert!(report.is_valid(), "{:?}", report);
+            //The second image is taken without calibrating again.
+            let instrument0 = objects.get("instrument0").unwrap();
+            assert_eq!(plan.iter().filter(|op| match op { Calibrate(_, instrument, _) => *instrument == instrument0, _ => false }).count(), 1);
+        }
     }
-}
 
+    #[test]
+    fn schedule_one_calibrates_at_the_target_when_already_pointing() {
+        let (mut state, goal, objects) = make_satellite_problem_from_str(TWO_SATELLITES).unwrap();
+        let satellite0 = objects.get("satellite0").unwrap();
+        let instrument0 = objects.get("instrument0").unwrap();
+        let image0 = objects.get("image0").unwrap();
+        let star0 = objects.get("star0").unwrap();
+        let star1 = objects.get("star1").unwrap();
+        assert!(TurnTo(satellite0, star1, star0).attempt_update(&mut state));
+        assert_eq!(steps(ScheduleOne(satellite0, instrument0, image0, star1, star1).apply(&state, &goal)),
+                   vec![vec![Err(Switching(satellite0, instrument0)),
+                             Ok(TurnTo(satellite0, star0, star1)),
+                             Ok(Calibrate(satellite0, instrument0, star0)),
+                             Ok(TurnTo(satellite0, star1, star0)),
+                             Ok(TakeImage(satellite0, star1, instrument0, image0))]]);
+    }
+
+    #[test]
+    fn heuristics_charge_at_the_metric_rates() {
+        let problem = |metric: &str| format!("(define (problem rates) (:domain satellite)
+            (:objects satellite0 - satellite instrument0 - instrument image0 - mode star0 - direction star1 - direction)
+            (:init (on_board instrument0 satellite0) (supports instrument0 image0) (calibration_target instrument0 star0)
+                   (power_avail satellite0) (pointing satellite0 star0)
+                   (= (slew_time star0 star1) 10) (= (slew_time star1 star0) 10)
+                   (= (fuel satellite0) 100) (= (data_capacity satellite0) 100) (= (data star1 image0) 20)
+                   (= (data-stored) 0) (= (fuel-used) 0))
+            (:goal (and (have_image star1 image0)))
+            (:metric {}))", metric);
+        let distance = |metric: &str, heuristic: Heuristic| {
+            let (mut state, goal, _) = make_satellite_problem_from_str(problem(metric).as_str()).unwrap();
+            state.config.heuristic = heuristic;
+            goal.distance_from(&state)
+        };
+        for heuristic in [Heuristic::Admissible, Heuristic::Fast].iter() {
+            let fuel_used = distance("minimize (fuel-used)", *heuristic);
+            //The only turn is the 10 from star0 to star1.
+            assert_eq!(distance("minimize (* 3 (fuel-used))", *heuristic), fuel_used + I40F24::from_num(20));
+            assert_eq!(distance("minimize (+ (fuel-used) (data-stored))", *heuristic), fuel_used + I40F24::from_num(20));
+            //Turning earns fuel-used, which Operator::cost charges nothing for.
+            assert_eq!(distance("maximize (fuel-used)", *heuristic), fuel_used - I40F24::from_num(10));
+        }
+    }
+
+    #[test]
+    fn satellites_without_a_direction_do_not_break_pointing_goals() {
+        let problem = TWO_SATELLITES.replace("satellite1 - satellite", "satellite1 - satellite satellite2 - satellite")
+            .replace("(have_image star1 image0)", "(pointing satellite2 star1)");
+        let (mut state, goal, _) = make_satellite_problem_from_str(problem.as_str()).unwrap();
+        for heuristic in [Heuristic::GoalCount, Heuristic::Admissible, Heuristic::Fast].iter() {
+            state.config.heuristic = *heuristic;
+            goal.distance_from(&state);
+        }
+        assert_eq!(steps(ScheduleAll.apply(&state, &goal)), vec![vec![]]);
+    }
+
+    #[test]
+    fn admissible_heuristic_never_exceeds_the_fast_one() {
+        let problem = TWO_SATELLITES.replace("(have_image star1 image0)", "(have_image star1 image0) (have_image star2 image0)");
+        let (mut state, goal, _) = make_satellite_problem_from_str(problem.as_str()).unwrap();
+        state.config.costs.calibrate = I40F24::from_num(2);
+        state.config.costs.switch_on = I40F24::from_num(3);
+        //satellite0 is ready to calibrate, then needs one turn to either star.
+        state.config.heuristic = Heuristic::Admissible;
+        assert_eq!(goal.distance_from(&state), I40F24::from_num(3));
+        state.config.heuristic = Heuristic::Fast;
+        assert_eq!(goal.distance_from(&state), I40F24::from_num(6));
+        state.config.heuristic = Heuristic::GoalCount;
+        assert_eq!(goal.distance_from(&state), I40F24::from_num(2));
+    }
+}
@@ -0,0 +1,310 @@
+use std::fmt;
+use std::str::FromStr;
+
+use fixed::types::I40F24;
+use log::warn;
+
+use crate::object_table::{NamedDisplay, ObjectTable, ObjectType};
+use crate::operators::{SatelliteEnum, SatelliteState};
+use crate::parse_error::SatelliteParseError;
+use crate::pddl_parser::canonical_name;
+use crate::sexpr::{atom, read_sexpr, tokenize, SExpr};
+
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub enum Optimization {
+    Minimize,
+    Maximize,
+}
+
+//A numeric expression over the fluents of a SatelliteState, as written in a problem's :metric.
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub enum MetricExpr {
+    Number(I40F24),
+    FuelUsed,
+    DataStored,
+    //satellite
+    Fuel(SatelliteEnum),
+    //satellite
+    DataCapacity(SatelliteEnum),
+    Add(Vec<MetricExpr>),
+    Mul(Vec<MetricExpr>),
+    Sub(Box<MetricExpr>, Box<MetricExpr>),
+    Div(Box<MetricExpr>, Box<MetricExpr>),
+    Neg(Box<MetricExpr>),
+}
+
+impl MetricExpr {
+    pub fn evaluate(&self, state: &SatelliteState) -> I40F24 {
+        use MetricExpr::*;
+        let zero = I40F24::from_num(0);
+        match self {
+            Number(n) => *n,
+            FuelUsed => state.fuel_used,
+            DataStored => state.total_data_stored,
+            Fuel(satellite) => state.fuel.get(satellite).copied().unwrap_or(zero),
+            DataCapacity(satellite) => state.data_capacity.get(satellite).copied().unwrap_or(zero),
+            Add(terms) => terms.iter().fold(zero, |total, term| total.saturating_add(term.evaluate(state))),
+            Mul(terms) => terms.iter().fold(I40F24::from_num(1), |total, term| total.saturating_mul(term.evaluate(state))),
+            Sub(a, b) => a.evaluate(state).saturating_sub(b.evaluate(state)),
+            Div(a, b) => match a.evaluate(state).checked_div(b.evaluate(state)) {
+                Some(x) => x,
+                None => {
+                    warn!("Division by zero in the metric {:?}", self);
+                    zero
+                }
+            },
+            Neg(a) => zero.saturating_sub(a.evaluate(state)),
+        }
+    }
+
+    //The value of an expression without fluents, e.g. (* 2 3).
+    pub fn constant_value(&self) -> Option<I40F24> {
+        use MetricExpr::*;
+        match self {
+            Number(n) => Some(*n),
+            FuelUsed | DataStored | Fuel(_) | DataCapacity(_) => None,
+            Add(terms) => terms.iter().try_fold(I40F24::from_num(0), |total, term| Some(total.saturating_add(term.constant_value()?))),
+            Mul(terms) => terms.iter().try_fold(I40F24::from_num(1), |total, term| Some(total.saturating_mul(term.constant_value()?))),
+            Sub(a, b) => Some(a.constant_value()?.saturating_sub(b.constant_value()?)),
+            Div(a, b) => a.constant_value()?.checked_div(b.constant_value()?),
+            Neg(a) => Some(I40F24::from_num(0).saturating_sub(a.constant_value()?)),
+        }
+    }
+
+    //How much the expression grows when the fluent grows by 1, or None if that depends on the
+    //state, as in (* (fuel-used) (data-stored)).
+    pub fn rate(&self, fluent: &MetricExpr) -> Option<I40F24> {
+        use MetricExpr::*;
+        let zero = I40F24::from_num(0);
+        match self {
+            Number(_) => Some(zero),
+            FuelUsed | DataStored | Fuel(_) | DataCapacity(_) => Some(if self == fluent { I40F24::from_num(1) } else { zero }),
+            Add(terms) => terms.iter().try_fold(zero, |total, term| Some(total.saturating_add(term.rate(fluent)?))),
+            Mul(terms) => {
+                let (variable, constant): (Vec<&MetricExpr>, Vec<&MetricExpr>) = terms.iter().partition(|term| term.constant_value().is_none());
+                let factor = constant.iter().fold(I40F24::from_num(1), |total, term| total.saturating_mul(term.constant_value().unwrap()));
+                match variable.as_slice() {
+                    [] => Some(zero),
+                    [term] => Some(factor.saturating_mul(term.rate(fluent)?)),
+                    _ => None,
+                }
+            }
+            Sub(a, b) => Some(a.rate(fluent)?.saturating_sub(b.rate(fluent)?)),
+            Div(a, b) => a.rate(fluent)?.checked_div(b.constant_value()?),
+            Neg(a) => Some(zero.saturating_sub(a.rate(fluent)?)),
+        }
+    }
+
+    //Sums of constant multiples of fluents, which are the metrics a step's cost can be worked out
+    //for from its effects alone.
+    pub fn is_linear(&self) -> bool {
+        self.rate(&MetricExpr::FuelUsed).is_some()
+    }
+}
+
+//The (:metric minimize ...) or (:metric maximize ...) of a problem.
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub struct PlanMetric {
+    pub optimization: Optimization,
+    pub expr: MetricExpr,
+}
+
+impl PlanMetric {
+    pub fn new(optimization: Optimization, expr: MetricExpr) -> Self {
+        PlanMetric { optimization, expr }
+    }
+
+    //The metric of the IPC 2002 numeric problems: (:metric minimize (fuel-used))
+    pub fn fuel_used() -> Self {
+        PlanMetric::new(Optimization::Minimize, MetricExpr::FuelUsed)
+    }
+
+    //The value of the metric expression in this state.
+    pub fn evaluate(&self, state: &SatelliteState) -> I40F24 {
+        self.expr.evaluate(state)
+    }
+
+    //The value in a form where lower is always better, which is what the planner minimizes.
+    pub fn cost(&self, state: &SatelliteState) -> I40F24 {
+        match self.optimization {
+            Optimization::Minimize => self.evaluate(state),
+            Optimization::Maximize => I40F24::from_num(0).saturating_sub(self.evaluate(state)),
+        }
+    }
+
+    //The cost of each unit of slew time the satellite turns through, which it takes from the
+    //satellite's fuel and adds to fuel-used. Negative when turning improves the metric.
+    pub fn turn_rate(&self, satellite: &SatelliteEnum) -> I40F24 {
+        self.per_unit(MetricExpr::FuelUsed, MetricExpr::Fuel(*satellite))
+    }
+
+    //The cost of each unit of image data, which take_image takes from the satellite's
+    //data_capacity and adds to data-stored.
+    pub fn image_rate(&self, satellite: &SatelliteEnum) -> I40F24 {
+        self.per_unit(MetricExpr::DataStored, MetricExpr::DataCapacity(*satellite))
+    }
+
+    fn per_unit(&self, gained: MetricExpr, spent: MetricExpr) -> I40F24 {
+        let zero = I40F24::from_num(0);
+        let rate = |fluent: &MetricExpr| self.expr.rate(fluent).unwrap_or(zero);
+        let growth = rate(&gained).saturating_sub(rate(&spent));
+        match self.optimization {
+            Optimization::Minimize => growth,
+            Optimization::Maximize => zero.saturating_sub(growth),
+        }
+    }
+
+    //Finds and parses the :metric section of a problem, if it has one.
+    pub fn from_problem(contents: &str, objects: &ObjectTable) -> Result<Option<PlanMetric>, SatelliteParseError> {
+        let tokens = tokenize(contents);
+        let (define, _) = read_sexpr(&tokens).map_err(|reason| SatelliteParseError::Malformed { item: String::from("problem"), reason })?;
+        let sexpr = match define.tagged("define").and_then(|sections| sections.iter().find(|section| section.tagged(":metric").is_some())) {
+            Some(sexpr) => sexpr,
+            None => return Ok(None),
+        };
+        match sexpr {
+            SExpr::List(items) if items.len() == 3 => {
+                let optimization = match atom(&items[1]).map(|a| a.to_lowercase()).as_ref().map(|a| a.as_str()) {
+                    Some("minimize") => Optimization::Minimize,
+                    Some("maximize") => Optimization::Maximize,
+                    _ => return Err(bad_metric(&sexpr.to_string(), String::from("expected minimize or maximize"))),
+                };
+                let expr = to_expr(&items[2], objects).map_err(|reason| bad_metric(&sexpr.to_string(), reason))?;
+                if !expr.is_linear() {
+                    return Err(bad_metric(&sexpr.to_string(), String::from("only sums of constant multiples of fluents can be charged step by step")));
+                }
+                let metric = PlanMetric::new(optimization, expr);
+                //anyhop needs step costs of at least zero, so Operator::cost charges nothing for these.
+                let zero = I40F24::from_num(0);
+                if objects.objects_of_type(ObjectType::Satellite).iter().any(|satellite| metric.turn_rate(satellite) < zero || metric.image_rate(satellite) < zero) {
+                    warn!("{} rewards some steps, which will cost nothing instead", sexpr);
+                }
+                Ok(Some(metric))
+            }
+            _ => Err(bad_metric(&sexpr.to_string(), String::from("expected (:metric minimize|maximize <expression>)"))),
+        }
+    }
+}
+
+impl NamedDisplay for MetricExpr {
+    //The PDDL form, using the hyphenated names of the IPC 2002 domain, e.g. (* 2 (fuel-used))
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result {
+        use MetricExpr::*;
+        match self {
+            Number(n) => write!(f, "{}", n),
+            FuelUsed => write!(f, "(fuel-used)"),
+            DataStored => write!(f, "(data-stored)"),
+            Fuel(satellite) => write!(f, "(fuel {})", table.named(satellite)),
+            DataCapacity(satellite) => write!(f, "(data_capacity {})", table.named(satellite)),
+            Add(terms) => write_list(table, f, "+", &terms.iter().collect::<Vec<_>>()),
+            Mul(terms) => write_list(table, f, "*", &terms.iter().collect::<Vec<_>>()),
+            Sub(a, b) => write_list(table, f, "-", &[&**a, &**b]),
+            Div(a, b) => write_list(table, f, "/", &[&**a, &**b]),
+            Neg(a) => write_list(table, f, "-", &[&**a]),
+        }
+    }
+}
+
+fn write_list(table: &ObjectTable, f: &mut fmt::Formatter, op: &str, terms: &[&MetricExpr]) -> fmt::Result {
+    write!(f, "({}", op)?;
+    for term in terms.iter() {
+        write!(f, " ")?;
+        term.fmt_named(table, f)?;
+    }
+    write!(f, ")")
+}
+
+impl NamedDisplay for PlanMetric {
+    //e.g. (:metric minimize (fuel-used)), which PlanMetric::from_problem reads back.
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result {
+        let optimization = match self.optimization {
+            Optimization::Minimize => "minimize",
+            Optimization::Maximize => "maximize",
+        };
+        write!(f, "(:metric {} {})", optimization, table.named(&self.expr))
+    }
+}
+
+fn bad_metric(metric: &str, reason: String) -> SatelliteParseError {
+    SatelliteParseError::BadMetric { metric: String::from(metric.lines().next().unwrap_or("").trim()), reason }
+}
+
+fn to_expr(sexpr: &SExpr, objects: &ObjectTable) -> Result<MetricExpr, String> {
+    match sexpr {
+        SExpr::Atom(a) => match I40F24::from_str(a) {
+            Ok(n) => Ok(MetricExpr::Number(n)),
+            Err(_) => match canonical_name(a).as_str() {
+                //Some generators leave the parentheses off zero-argument functions.
+                "fuel_used" => Ok(MetricExpr::FuelUsed),
+                "data_stored" => Ok(MetricExpr::DataStored),
+                "total_time" => Err(String::from("total-time has no meaning in this domain")),
+                _ => Err(format!("unknown term {}", a)),
+            },
+        },
+        SExpr::List(items) => {
+            let head = match items.first().and_then(atom) {
+                //- is subtraction, not a hyphen to canonicalize.
+                Some("-") => String::from("-"),
+                Some(head) => canonical_name(head),
+                None => return Err(format!("expected an operator or function in {}", sexpr)),
+            };
+            let args = &items[1..];
+            let sub_exprs = || args.iter().map(|arg| to_expr(arg, objects)).collect::<Result<Vec<MetricExpr>, String>>();
+            match head.as_str() {
+                "fuel_used" if args.is_empty() => Ok(MetricExpr::FuelUsed),
+                "data_stored" if args.is_empty() => Ok(MetricExpr::DataStored),
+                "fuel" if args.len() == 1 => Ok(MetricExpr::Fuel(satellite_arg(&args[0], objects)?)),
+                "data_capacity" if args.len() == 1 => Ok(MetricExpr::DataCapacity(satellite_arg(&args[0], objects)?)),
+                "+" if !args.is_empty() => Ok(MetricExpr::Add(sub_exprs()?)),
+                "*" if !args.is_empty() => Ok(MetricExpr::Mul(sub_exprs()?)),
+                "-" if args.len() == 1 => Ok(MetricExpr::Neg(Box::new(to_expr(&args[0], objects)?))),
+                "-" if args.len() == 2 => Ok(MetricExpr::Sub(Box::new(to_expr(&args[0], objects)?), Box::new(to_expr(&args[1], objects)?))),
+                "/" if args.len() == 2 => Ok(MetricExpr::Div(Box::new(to_expr(&args[0], objects)?), Box::new(to_expr(&args[1], objects)?))),
+                _ => Err(format!("cannot evaluate {}", sexpr)),
+            }
+        }
+    }
+}
+
+fn satellite_arg(sexpr: &SExpr, objects: &ObjectTable) -> Result<SatelliteEnum, String> {
+    let name = atom(sexpr).ok_or_else(|| format!("expected a satellite, found {}", sexpr))?;
+    match objects.get(name) {
+        Some(item) if ObjectType::of(&item) == ObjectType::Satellite => Ok(item),
+        Some(_) => Err(format!("{} is not a satellite", name)),
+        None => Err(format!("unknown object {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weighted_sum() {
+        let mut objects = ObjectTable::new();
+        let satellite = objects.insert("satellite0", ObjectType::Satellite);
+        let problem = "(define (problem p) (:domain satellite) (:goal (and))\n (:metric minimize (+ (* 3 (fuel-used)) (* 2 (data-stored)) (fuel satellite0))))";
+        let metric = PlanMetric::from_problem(problem, &objects).unwrap().unwrap();
+        let three = MetricExpr::Number(I40F24::from_num(3));
+        let two = MetricExpr::Number(I40F24::from_num(2));
+        assert_eq!(metric, PlanMetric::new(Optimization::Minimize, MetricExpr::Add(vec![MetricExpr::Mul(vec![three, MetricExpr::FuelUsed]),
+                                                                                           MetricExpr::Mul(vec![two, MetricExpr::DataStored]),
+                                                                                           MetricExpr::Fuel(satellite)])));
+        assert_eq!(PlanMetric::from_problem("(define (problem p) (:domain satellite))", &objects).unwrap(), None);
+        let in_problem = |metric: &str| format!("(define (problem p) (:domain satellite) (:goal (and))\n {}\n)", metric);
+        assert!(PlanMetric::from_problem(in_problem("(:metric minimize (fuel satellite1))").as_str(), &objects).is_err());
+        //Only the :metric section counts, in any case, and not one in a comment.
+        assert_eq!(PlanMetric::from_problem(in_problem("; (:metric minimize (fuel satellite1))").as_str(), &objects).unwrap(), None);
+        assert!(PlanMetric::from_problem(in_problem("(:Metric maximize (data-stored))").as_str(), &objects).unwrap().is_some());
+
+        assert_eq!(metric.turn_rate(&satellite), I40F24::from_num(2));
+        assert_eq!(metric.image_rate(&satellite), I40F24::from_num(2));
+        //Keeping fuel costs a unit per unit of slew time when the metric maximizes it.
+        assert_eq!(PlanMetric::new(Optimization::Maximize, MetricExpr::Fuel(satellite)).turn_rate(&satellite), I40F24::from_num(1));
+        assert!(PlanMetric::from_problem(in_problem("(:metric minimize (* (fuel-used) (data-stored)))").as_str(), &objects).is_err());
+
+        let written = objects.named(&metric).to_string();
+        assert_eq!(written, "(:metric minimize (+ (* 3 (fuel-used)) (* 2 (data-stored)) (fuel satellite0)))");
+        assert_eq!(PlanMetric::from_problem(in_problem(written.as_str()).as_str(), &objects).unwrap(), Some(metric));
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use fixed::types::I40F24;
+
+use crate::operators::{SatelliteEnum, SatelliteOperator};
+
+//The object types declared by the Satellite domain.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub enum ObjectType {
+    Satellite,
+    Instrument,
+    Mode,
+    Direction,
+}
+
+impl ObjectType {
+    pub fn from_pddl(type_name: &str) -> Option<ObjectType> {
+        match type_name.to_lowercase().as_str() {
+            "satellite" => Some(ObjectType::Satellite),
+            "instrument" => Some(ObjectType::Instrument),
+            "mode" => Some(ObjectType::Mode),
+            "direction" => Some(ObjectType::Direction),
+            _ => None,
+        }
+    }
+
+    pub fn pddl_name(&self) -> &'static str {
+        match self {
+            ObjectType::Satellite => "satellite",
+            ObjectType::Instrument => "instrument",
+            ObjectType::Mode => "mode",
+            ObjectType::Direction => "direction",
+        }
+    }
+
+    //Wraps an id in the SatelliteEnum variant for this type.
+    pub fn make(&self, id: I40F24) -> SatelliteEnum {
+        match self {
+            ObjectType::Satellite => SatelliteEnum::Satellite(id),
+            ObjectType::Instrument => SatelliteEnum::Instrument(id),
+            ObjectType::Mode => SatelliteEnum::Mode(id),
+            ObjectType::Direction => SatelliteEnum::Direction(id),
+        }
+    }
+
+    pub fn of(item: &SatelliteEnum) -> ObjectType {
+        match item {
+            SatelliteEnum::Satellite(_) => ObjectType::Satellite,
+            SatelliteEnum::Instrument(_) => ObjectType::Instrument,
+            SatelliteEnum::Mode(_) => ObjectType::Mode,
+            SatelliteEnum::Direction(_) => ObjectType::Direction,
+        }
+    }
+}
+
+//Bidirectional lookup between the PDDL object names of a problem and the I40F24 ids
+//that SatelliteEnum carries around in their place. Each object keeps its declared type.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ObjectTable {
+    name2id: BTreeMap<String, I40F24>,
+    id2name: BTreeMap<I40F24, String>,
+    id2type: BTreeMap<I40F24, ObjectType>,
+}
+
+impl ObjectTable {
+    pub fn new() -> Self {
+        ObjectTable::default()
+    }
+
+    //Returns the object, assigning the next free id if the name is new. A name keeps the type
+    //it was first inserted with.
+    pub fn insert(&mut self, name: &str, obj_type: ObjectType) -> SatelliteEnum {
+        if let Some(item) = self.get(name) {
+            return item;
+        }
+        let id = I40F24::from_num(self.name2id.len());
+        self.name2id.insert(String::from(name), id);
+        self.id2name.insert(id, String::from(name));
+        self.id2type.insert(id, obj_type);
+        obj_type.make(id)
+    }
+
+    pub fn id_of(&self, name: &str) -> Option<I40F24> {
+        self.name2id.get(name).copied()
+    }
+
+    pub fn type_of(&self, name: &str) -> Option<ObjectType> {
+        self.id_of(name).and_then(|id| self.id2type.get(&id).copied())
+    }
+
+    //The object with its declared type, e.g. Satellite(0) for satellite0.
+    pub fn get(&self, name: &str) -> Option<SatelliteEnum> {
+        let id = self.id_of(name)?;
+        Some(self.id2type.get(&id)?.make(id))
+    }
+
+    //All objects of one type, in id order.
+    pub fn objects_of_type(&self, obj_type: ObjectType) -> Vec<SatelliteEnum> {
+        self.id2type.iter()
+            .filter(|(_, t)| **t == obj_type)
+            .map(|(id, t)| t.make(*id))
+            .collect()
+    }
+
+    pub fn name_of(&self, id: I40F24) -> Option<&str> {
+        self.id2name.get(&id).map(|name| name.as_str())
+    }
+
+    pub fn name_of_enum(&self, item: &SatelliteEnum) -> Option<&str> {
+        self.name_of(item.id())
+    }
+
+    pub fn len(&self) -> usize {
+        self.name2id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name2id.is_empty()
+    }
+
+    //Object names in id order.
+    pub fn names(&self) -> impl Iterator<Item=&str> {
+        self.id2name.values().map(|name| name.as_str())
+    }
+
+    //Wraps an item so that formatting it with {} prints the original PDDL names.
+    pub fn named<'a, T: NamedDisplay>(&'a self, item: &'a T) -> Named<'a, T> {
+        Named { table: self, item }
+    }
+}
+
+//Anything that can be rendered with the object names from an ObjectTable.
+pub trait NamedDisplay {
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+pub struct Named<'a, T> {
+    table: &'a ObjectTable,
+    item: &'a T,
+}
+
+impl<'a, T: NamedDisplay> fmt::Display for Named<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.item.fmt_named(self.table, f)
+    }
+}
+
+impl NamedDisplay for SatelliteEnum {
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result {
+        match table.name_of_enum(self) {
+            Some(name) => write!(f, "{}", name),
+            //Fall back on the raw id so that a bad lookup is still visible in the output.
+            None => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl NamedDisplay for SatelliteOperator<SatelliteEnum> {
+    //Prints the action the way it appears in the domain, e.g. turn_to satellite0 star5 groundstation2
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.action_name())?;
+        for arg in self.args() {
+            write!(f, " ")?;
+            arg.fmt_named(table, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: NamedDisplay> NamedDisplay for Vec<T> {
+    //One item per line, which is how plans are usually read.
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result {
+        for item in self.iter() {
+            item.fmt_named(table, f)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operators::SatelliteOperator::{SwitchOn, TurnTo};
+    use crate::pddl_parser::make_satellite_problem_from;
+
+    #[test]
+    fn operators_print_with_their_pddl_names() {
+        let (_, _, objects) = make_satellite_problem_from("pfiles/pfile1").unwrap();
+        for name in objects.names() {
+            let item = objects.get(name).unwrap();
+            assert_eq!(objects.name_of_enum(&item), Some(name));
+            assert_eq!(objects.type_of(name), Some(ObjectType::of(&item)));
+        }
+        assert_eq!(objects.get("satellite0"), Some(SatelliteEnum::Satellite(objects.id_of("satellite0").unwrap())));
+        assert_eq!(objects.get("satellite7"), None);
+
+        let satellite0 = objects.get("satellite0").unwrap();
+        let turn = TurnTo(satellite0, objects.get("star5").unwrap(), objects.get("groundstation2").unwrap());
+        assert_eq!(objects.named(&turn).to_string(), "turn_to satellite0 star5 groundstation2");
+        let plan = vec![SwitchOn(objects.get("instrument0").unwrap(), satellite0), turn];
+        assert_eq!(objects.named(&plan).to_string(), "switch_on instrument0 satellite0\nturn_to satellite0 star5 groundstation2\n");
+    }
+}
@@ -1,11 +1,13 @@
 use std::collections::{BTreeMap, BTreeSet};
 
-use anyhop::{Atom, Operator, CmdArgs};
+use anyhop::{Atom, Operator};
 use strum_macros::*;
 use fixed::types::I40F24;
 use log::{debug, error, info, trace, warn};
 
 use crate::methods::SatelliteStatus;
+use crate::metric::PlanMetric;
+use crate::validator::validate_plan;
 use crate::methods::SatelliteStatus::{Done, NotDone};
 
 #[derive(Clone, Copy, PartialOrd, PartialEq, Ord, Eq, Debug, Display)]
@@ -18,6 +20,116 @@ pub enum SatelliteEnum {
     Direction(I40F24),
 }
 
+impl SatelliteEnum {
+    //The raw object id, regardless of which kind of object this is.
+    pub fn id(&self) -> I40F24 {
+        use SatelliteEnum::*;
+        match self {
+            Instrument(id) | Satellite(id) | Mode(id) | Direction(id) => *id,
+        }
+    }
+}
+
+//Which version of the IPC 2002 Satellite domain the operators follow. The STRIPS version has no fuel,
+//so turning is always possible.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug, EnumString, Display)]
+pub enum Semantics {
+    #[strum(serialize="strips")]
+    Strips,
+    #[strum(serialize="numeric")]
+    Numeric,
+}
+
+//What the planner is charged for each operator other than turn_to, which always costs its slew time.
+//These are all zero by default, so that the cost of a plan is the fuel it uses.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub struct OperatorCosts {
+    pub switch_on: I40F24,
+    pub switch_off: I40F24,
+    pub calibrate: I40F24,
+    pub take_image: I40F24,
+}
+
+impl OperatorCosts {
+    //Sets the cost of an action by its PDDL name, returning false if there is no such action.
+    pub fn set(&mut self, action: &str, cost: I40F24) -> bool {
+        match action {
+            "switch_on" => self.switch_on = cost,
+            "switch_off" => self.switch_off = cost,
+            "calibrate" => self.calibrate = cost,
+            "take_image" => self.take_image = cost,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Default for OperatorCosts {
+    fn default() -> Self {
+        let zero = I40F24::from_num(0);
+        OperatorCosts { switch_on: zero, switch_off: zero, calibrate: zero, take_image: zero }
+    }
+}
+
+//Ways for ScheduleAll to rank the images it could take next. Each ordering it is given contributes
+//its favourite choices to the alternatives the planner explores.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug, EnumString, Display)]
+pub enum GoalOrdering {
+    //Direction order, which is how the goals are stored.
+    #[strum(serialize="key")]
+    KeyOrder,
+    //Least turning first, from wherever the satellite is pointing now.
+    #[strum(serialize="nearest")]
+    NearestSlew,
+    //Instruments that are already powered on first, so that calibrations are reused.
+    #[strum(serialize="instrument")]
+    ByInstrument,
+    //Satellites that are already working first, so that each finishes before the next starts.
+    #[strum(serialize="satellite")]
+    BySatellite,
+}
+
+//How SatelliteGoals::distance_from estimates the cost still to come.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug, EnumString, Display)]
+pub enum Heuristic {
+    //The number of unmet goals, which ignores fuel entirely.
+    #[strum(serialize="count")]
+    GoalCount,
+    //Never more than the real remaining cost: the dearest single open goal, plus taking each image.
+    #[strum(serialize="admissible")]
+    Admissible,
+    //The cheapest way to reach each open goal on its own, summed. Overestimates when goals share
+    //turns or calibrations, but separates states more sharply.
+    #[strum(serialize="fast")]
+    Fast,
+}
+
+//Settings that change how the domain behaves, carried by each state so that problems with
+//different settings can be planned side by side.
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub struct DomainConfig {
+    pub semantics: Semantics,
+    pub costs: OperatorCosts,
+    pub orderings: Vec<GoalOrdering>,
+    //The most alternatives ScheduleAll offers at once, at least 1; None offers every one.
+    pub branching_limit: Option<usize>,
+    pub heuristic: Heuristic,
+}
+
+impl DomainConfig {
+    pub fn new(semantics: Semantics) -> Self {
+        DomainConfig { semantics, costs: OperatorCosts::default(),
+            orderings: vec![GoalOrdering::NearestSlew, GoalOrdering::ByInstrument, GoalOrdering::BySatellite, GoalOrdering::KeyOrder],
+            branching_limit: None, heuristic: Heuristic::Admissible }
+    }
+}
+
+impl Default for DomainConfig {
+    fn default() -> Self {
+        DomainConfig::new(Semantics::Numeric)
+    }
+}
+
 #[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
 pub struct SatelliteState {
     //map satellite -> vec<instrument>
@@ -31,8 +143,8 @@ pub struct SatelliteState {
     pub power_on: Vec<SatelliteEnum>,
     //instrument
     pub calibrated: Vec<SatelliteEnum>,
-    //direction -> mode
-    pub have_image: BTreeMap<SatelliteEnum, SatelliteEnum>,
+    //(direction, mode): a direction can be imaged in several modes
+    pub have_image: BTreeSet<(SatelliteEnum, SatelliteEnum)>,
     //instrument -> direction
     pub calibration_target: BTreeMap<SatelliteEnum, SatelliteEnum>,
     //map satelite -> I40F24
@@ -45,16 +157,20 @@ pub struct SatelliteState {
     //satellite -> fuel
     pub fuel: BTreeMap<SatelliteEnum, I40F24>,
     pub status: SatelliteStatus,
+    pub config: DomainConfig,
 }
 
 impl SatelliteState {
-    pub fn new(onboard: BTreeMap<SatelliteEnum, Vec<SatelliteEnum>>, supports: BTreeMap<SatelliteEnum, Vec<SatelliteEnum>>, pointing: BTreeMap<SatelliteEnum, SatelliteEnum>, power_avail: BTreeMap<SatelliteEnum, bool>, power_on: Vec<SatelliteEnum>, calibrated: Vec<SatelliteEnum>, have_image: BTreeMap<SatelliteEnum, SatelliteEnum>, calibration_target: BTreeMap<SatelliteEnum, SatelliteEnum>, data_capacity: BTreeMap<SatelliteEnum, I40F24>, total_data_stored: I40F24, satellite_data_stored: BTreeMap<(SatelliteEnum, SatelliteEnum), I40F24>, slew_time: BTreeMap<(SatelliteEnum, SatelliteEnum), I40F24>, fuel_used: I40F24, fuel: BTreeMap<SatelliteEnum, I40F24>) -> Self {
-        SatelliteState { onboard, supports, pointing, power_avail, power_on, calibrated, have_image, calibration_target, data_capacity, total_data_stored, satellite_data_stored, slew_time, fuel_used, fuel, status: (Done) }
+    pub fn new(onboard: BTreeMap<SatelliteEnum, Vec<SatelliteEnum>>, supports: BTreeMap<SatelliteEnum, Vec<SatelliteEnum>>, pointing: BTreeMap<SatelliteEnum, SatelliteEnum>, power_avail: BTreeMap<SatelliteEnum, bool>, power_on: Vec<SatelliteEnum>, calibrated: Vec<SatelliteEnum>, have_image: BTreeSet<(SatelliteEnum, SatelliteEnum)>, calibration_target: BTreeMap<SatelliteEnum, SatelliteEnum>, data_capacity: BTreeMap<SatelliteEnum, I40F24>, total_data_stored: I40F24, satellite_data_stored: BTreeMap<(SatelliteEnum, SatelliteEnum), I40F24>, slew_time: BTreeMap<(SatelliteEnum, SatelliteEnum), I40F24>, fuel_used: I40F24, fuel: BTreeMap<SatelliteEnum, I40F24>) -> Self {
+        SatelliteState { onboard, supports, pointing, power_avail, power_on, calibrated, have_image, calibration_target, data_capacity, total_data_stored, satellite_data_stored, slew_time, fuel_used, fuel, status: (Done), config: DomainConfig::default() }
     }
 }
 
 
 impl SatelliteState {
+    pub fn set_semantics(&mut self, semantics: Semantics) {
+        self.config.semantics = semantics;
+    }
     //data_capacity
     pub fn set_data_capacity(&mut self, satellite: SatelliteEnum, capacity: I40F24) {
         self.data_capacity.insert(satellite, capacity);
@@ -82,15 +198,27 @@ impl SatelliteState {
     //  things so that we are lending it.
     pub fn turn_to(&mut self, satellite: &SatelliteEnum, new_direction: &SatelliteEnum, previous_direction: &SatelliteEnum) -> bool {
         if (self.pointing_helper(satellite, previous_direction)) && (new_direction != previous_direction) {
-            // GJF: *** I had to clone them here to create the key for the lookup.
-            let key = (new_direction.clone(), previous_direction.clone());
-            // GJF: *** Separate out the get to avoid the borrow conflict:
-            let slew_time = match self.slew_time.get(&key) {
-                Some(x) => *x,
-                None => panic!(format!("Error while turning: The following key lookup failed in the slew_time table: {} {}", &key.0, &key.1))
+            return match self.config.semantics {
+                Semantics::Strips => {
+                    self.pointing.insert(satellite.clone(), new_direction.clone());
+                    true
+                }
+                Semantics::Numeric => {
+                    // GJF: *** I had to clone them here to create the key for the lookup.
+                    let key = (new_direction.clone(), previous_direction.clone());
+                    // GJF: *** Separate out the get to avoid the borrow conflict:
+                    match self.slew_time.get(&key) {
+                        Some(x) => {
+                            let slew_time = *x;
+                            self.turn_to_helper(satellite, slew_time, new_direction, previous_direction)
+                        }
+                        None => {
+                            warn!("Turn_to failed: the slew_time table has no entry for {:?} {:?}", &key.0, &key.1);
+                            false
+                        }
+                    }
+                }
             };
-            self.turn_to_helper(satellite, slew_time, new_direction, previous_direction);
-            return true;
         }else {
             info!("Turn_to failed!");
             debug!("Pointing helper: {}", self.pointing_helper(satellite, previous_direction));
@@ -100,6 +228,18 @@ impl SatelliteState {
         }
     }
 
+    //The fuel needed to turn between two directions. Without a slew time, as in STRIPS problems,
+    //every turn counts as 1.
+    pub fn slew_cost(&self, new_direction: &SatelliteEnum, previous_direction: &SatelliteEnum) -> I40F24 {
+        if new_direction == previous_direction {
+            return I40F24::from_num(0);
+        }
+        match self.slew_time.get(&(*new_direction, *previous_direction)) {
+            Some(x) => *x,
+            None => I40F24::from_num(1),
+        }
+    }
+
     fn pointing_helper(&mut self, satellite: &SatelliteEnum, direction: &SatelliteEnum) -> bool {
         return match self.pointing.get(satellite) {
             Some(x) => x == direction, //If we have the correct instrument selected, we need to make sure that it is selected at the right direction.
@@ -108,19 +248,25 @@ impl SatelliteState {
     }
 
 
-    fn turn_to_helper(&mut self, satellite: &SatelliteEnum, x: I40F24, new_direction: &SatelliteEnum, previous_direction: &SatelliteEnum) {
-        let cmd_args = CmdArgs::new().unwrap();
-        let is_strips = cmd_args.has_tag("strips");
-        if !is_strips{
-            if self.fuel.get(satellite).unwrap() >= &x {
-                self.set_slew_time(new_direction, previous_direction, I40F24::from_num(self.fuel.get(satellite).unwrap() - I40F24::from_num(1)));
-                self.set_slew_time(new_direction, previous_direction, I40F24::from_num(self.fuel_used + I40F24::from_num(1)));
-                self.pointing.insert(satellite.clone(),new_direction.clone());
+    //Numeric turn_to: needs (>= (fuel ?s) (slew_time ?d_new ?d_prev)), then spends that fuel
+    //and adds it to fuel-used.
+    fn turn_to_helper(&mut self, satellite: &SatelliteEnum, slew_time: I40F24, new_direction: &SatelliteEnum, previous_direction: &SatelliteEnum) -> bool {
+        let fuel = match self.fuel.get(satellite) {
+            Some(x) => *x,
+            None => {
+                warn!("Turn_to failed: no fuel is recorded for {:?}", satellite);
+                return false;
             }
-        }else{
+        };
+        if fuel >= slew_time {
+            self.set_satellite_fuel(satellite, fuel - slew_time);
+            self.set_fuel_used(self.fuel_used + slew_time);
             self.pointing.insert(satellite.clone(),new_direction.clone());
+            return true;
+        } else {
+            info!("Turn_to failed: {:?} has {} fuel but turning from {:?} to {:?} needs {}", satellite, fuel, previous_direction, new_direction, slew_time);
+            return false;
         }
-
     }
     fn switch_on(&mut self, instrument: &SatelliteEnum, satellite: &SatelliteEnum) -> bool {
         //precondition
@@ -188,29 +334,33 @@ impl SatelliteState {
             None => false, //If the lookup fails, the if statement should fail.
         };
     }
+    //Implements (decrease (data_capacity ?s) (data ?d ?m)) and (increase (data-stored) (data ?d ?m)).
     pub fn take_image(&mut self, satellite: &SatelliteEnum, direction: SatelliteEnum, instrument: &SatelliteEnum, mode: &SatelliteEnum) -> bool {
         // GJF: *** To solve the ownership problem, we store the capacity in a local
         //      variable where it is copied out of self. We then use that local variable
         //      wherever we need it.
-        let satellite_capacity = *(self.data_capacity.get(satellite).unwrap());
+        let satellite_capacity = match self.data_capacity.get(satellite) {
+            Some(x) => *x,
+            None => I40F24::from_num(0),
+        };
+        let image_size = self.get_satellite_data_used(&direction, mode);
         if self.calibrated.contains(instrument) &&
             self.onboard.get(satellite).unwrap().contains(instrument) &&
             self.does_instrument_support_mode(instrument, mode) &&
             self.power_on.contains(instrument) &&
             self.pointing_helper(satellite, &direction) &&
-            satellite_capacity >= self.get_satellite_data_used(&direction, mode) {
+            satellite_capacity >= image_size {
 
             //reduce the capacity
-            let subtracted_capacity = satellite_capacity - self.get_satellite_data_used(&direction, mode);
-            self.data_capacity.insert(satellite.clone(), subtracted_capacity);
+            self.data_capacity.insert(satellite.clone(), satellite_capacity - image_size);
             //insert the image
-            self.have_image.insert(direction.clone(), mode.clone());
-            //update the capacity
-            let old_capacity = self.get_satellite_data_used(&direction, mode);
-            self.total_data_stored = old_capacity; //add old_capacity
+            self.have_image.insert((direction.clone(), mode.clone()));
+            //add the image to the running total
+            self.total_data_stored += image_size;
             return true;
         } else {
             warn!("Take image failed");
+            debug!("Capacity of {:?} is {}, image of {:?} in {:?} needs {}", satellite, satellite_capacity, direction, mode, image_size);
             return false;
         }
     }
@@ -231,26 +381,30 @@ impl SatelliteState {
 
 #[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
 pub struct SatelliteGoals {
-    //Have_image maps from location -> instrument
-    pub have_image: BTreeMap<SatelliteEnum, SatelliteEnum>,
+    //(direction, mode) pairs that must be imaged
+    pub have_image: BTreeSet<(SatelliteEnum, SatelliteEnum)>,
     //map satellite -> direction
     pub pointing: BTreeMap<SatelliteEnum, SatelliteEnum>,
-    pub fuel_used: I40F24,
+    //The problem's :metric, if it has one.
+    pub metric: Option<PlanMetric>,
 }
 
 impl SatelliteGoals {
-    pub fn new(have_image: BTreeMap<SatelliteEnum, SatelliteEnum>, pointing: BTreeMap<SatelliteEnum, SatelliteEnum>, fuel_used: I40F24) -> Self {
-        SatelliteGoals { have_image, pointing, fuel_used }
+    pub fn new(have_image: BTreeSet<(SatelliteEnum, SatelliteEnum)>, pointing: BTreeMap<SatelliteEnum, SatelliteEnum>, metric: Option<PlanMetric>) -> Self {
+        SatelliteGoals { have_image, pointing, metric }
+    }
+
+    //The value of the metric in this state, for reporting.
+    pub fn metric_value(&self, state: &SatelliteState) -> Option<I40F24> {
+        self.metric.as_ref().map(|metric| metric.evaluate(state))
     }
 }
 
 impl SatelliteGoals {
     
     pub fn all_met_in(&self, state:&SatelliteState) -> bool{
-        for (location,instrument) in self.have_image.iter(){
-            let state_instrument = state.have_image.get(location);
-
-            if state_instrument == None || state_instrument != Some(instrument) {
+        for image in self.have_image.iter(){
+            if !state.have_image.contains(image) {
                 warn!("We have failed the have_image checker!");
                 warn!("Goal have_image: {:?}", self.have_image);
                 warn!("Actual have_image: {:?}", state.have_image);
@@ -282,13 +436,67 @@ pub enum SatelliteOperator<SatelliteEnum> {
     TakeImage(SatelliteEnum, SatelliteEnum, SatelliteEnum, SatelliteEnum),
 }
 
+impl<E: Copy> SatelliteOperator<E> {
+    //The name of the action in the IPC 2002 Satellite domain.
+    pub fn action_name(&self) -> &'static str {
+        use SatelliteOperator::*;
+        match self {
+            TurnTo(..) => "turn_to",
+            SwitchOn(..) => "switch_on",
+            SwitchOff(..) => "switch_off",
+            Calibrate(..) => "calibrate",
+            TakeImage(..) => "take_image",
+        }
+    }
+
+    //The arguments in the same order as the parameters of the PDDL action.
+    pub fn args(&self) -> Vec<E> {
+        use SatelliteOperator::*;
+        match self {
+            TurnTo(satellite, new_direction, previous_direction) => vec![*satellite, *new_direction, *previous_direction],
+            SwitchOn(instrument, satellite) => vec![*instrument, *satellite],
+            SwitchOff(instrument, satellite) => vec![*instrument, *satellite],
+            Calibrate(satellite, instrument, direction) => vec![*satellite, *instrument, *direction],
+            TakeImage(satellite, direction, instrument, mode) => vec![*satellite, *direction, *instrument, *mode],
+        }
+    }
+}
+
 impl Operator for SatelliteOperator<SatelliteEnum> {
     type S = SatelliteState;
     type C = I40F24;
     type G = SatelliteGoals;
 
-    fn cost(&self, _state: &Self::S, _goal: &Self::G) -> Self::C {
-        return I40F24::from_num(1);
+    //A step costs however much it worsens the problem's metric, worked out from its own effects:
+    //turning spends its slew time as fuel, and take_image stores the image's data. Steps that would
+    //improve the metric cost nothing, as anyhop needs costs of at least zero. Without a metric, or
+    //under STRIPS semantics where the fluents never change, turning costs its slew time instead.
+    //Either way the state's OperatorCosts are added for the other operators.
+    fn cost(&self, state: &Self::S, goal: &Self::G) -> Self::C {
+        use SatelliteOperator::*;
+        let costs = &state.config.costs;
+        let zero = I40F24::from_num(0);
+        let (turn_cost, operator_cost) = match self {
+            TurnTo(_, new_direction, previous_direction) => (state.slew_cost(new_direction, previous_direction), zero),
+            SwitchOn(..) => (zero, costs.switch_on),
+            SwitchOff(..) => (zero, costs.switch_off),
+            Calibrate(..) => (zero, costs.calibrate),
+            TakeImage(..) => (zero, costs.take_image),
+        };
+        match &goal.metric {
+            Some(metric) if state.config.semantics == Semantics::Numeric => {
+                let change = match self {
+                    TurnTo(satellite, ..) => metric.turn_rate(satellite).saturating_mul(turn_cost),
+                    TakeImage(satellite, direction, _, mode) => {
+                        let image_size = state.satellite_data_stored.get(&(*direction, *mode)).copied().unwrap_or(zero);
+                        metric.image_rate(satellite).saturating_mul(image_size)
+                    }
+                    _ => zero,
+                };
+                change.max(zero).saturating_add(operator_cost)
+            }
+            _ => turn_cost.saturating_add(operator_cost),
+        }
     }
 
     fn zero_cost() -> Self::C {
@@ -307,9 +515,93 @@ impl Operator for SatelliteOperator<SatelliteEnum> {
     }
 }
 
+//See validator::validate_plan for the reasons a plan is not valid.
 pub fn is_satellite_valid(plan: &Vec<SatelliteOperator<SatelliteEnum>>, start: &SatelliteState, goal: &SatelliteGoals) -> bool {
+    validate_plan(plan, start, goal).is_valid()
+}
+//The state reached by applying every step of the plan, or None if a step cannot be applied.
+pub fn apply_plan(plan: &[SatelliteOperator<SatelliteEnum>], start: &SatelliteState) -> Option<SatelliteState> {
     let mut state = start.clone();
+    if plan.iter().all(|step| step.attempt_update(&mut state)) {
+        Some(state)
+    } else {
+        None
+    }
+}
 
-    let preconds_met = plan.iter().all(|step| step.attempt_update(&mut state));
-    preconds_met && goal.all_met_in(&state)
-}
\ No newline at end of file
+//Sums the cost of each step as the planner sees it, or returns None if a step cannot be applied.
+pub fn plan_cost(plan: &[SatelliteOperator<SatelliteEnum>], start: &SatelliteState, goal: &SatelliteGoals) -> Option<I40F24> {
+    let mut state = start.clone();
+    let mut total = I40F24::from_num(0);
+    for step in plan.iter() {
+        total += step.cost(&state, goal);
+        if !step.attempt_update(&mut state) {
+            return None;
+        }
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::SatelliteOperator::{Calibrate, SwitchOff, SwitchOn, TakeImage, TurnTo};
+    use crate::pddl_parser::make_satellite_problem_from_str;
+
+    //instrument0 is ready to image star1, whose image0 data takes 60 of the 100 capacity.
+    const READY_TO_IMAGE: &str = "(define (problem ready) (:domain satellite)
+        (:objects satellite0 - satellite instrument0 - instrument image0 - mode star0 - direction star1 - direction)
+        (:init (on_board instrument0 satellite0) (supports instrument0 image0) (calibration_target instrument0 star0)
+               (power_on instrument0) (calibrated instrument0) (pointing satellite0 star1)
+               (= (data_capacity satellite0) 100) (= (fuel satellite0) 10) (= (data star1 image0) 60)
+               (= (slew_time star0 star1) 1) (= (slew_time star1 star0) 1) (= (data-stored) 5) (= (fuel-used) 0))
+        (:goal (and (have_image star1 image0))))";
+
+    #[test]
+    fn take_image_stores_the_data_of_the_image() {
+        let (mut state, _, objects) = make_satellite_problem_from_str(READY_TO_IMAGE).unwrap();
+        let satellite0 = objects.get("satellite0").unwrap();
+        let star1 = objects.get("star1").unwrap();
+        let image0 = objects.get("image0").unwrap();
+        let take_image = TakeImage(satellite0, star1, objects.get("instrument0").unwrap(), image0);
+
+        assert!(take_image.attempt_update(&mut state));
+        assert_eq!(state.total_data_stored, I40F24::from_num(65));
+        assert_eq!(state.data_capacity.get(&satellite0), Some(&I40F24::from_num(40)));
+        assert!(state.have_image.contains(&(star1, image0)));
+
+        //Only 40 is left, so the same image does not fit a second time.
+        let before = state.clone();
+        assert!(!take_image.attempt_update(&mut state));
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn turns_cost_their_slew_time_and_other_operators_their_configured_cost() {
+        //Different slew times each way, so that a turn charged the wrong pair shows.
+        let problem = READY_TO_IMAGE.replace("(= (slew_time star0 star1) 1) (= (slew_time star1 star0) 1)", "(= (slew_time star0 star1) 7) (= (slew_time star1 star0) 3)")
+            .replace("(have_image star1 image0))))", "(have_image star1 image0)))\n (:metric minimize (fuel-used)))");
+        let (mut state, goal, objects) = make_satellite_problem_from_str(problem.as_str()).unwrap();
+        assert_eq!(goal.metric, Some(PlanMetric::fuel_used()));
+        let satellite0 = objects.get("satellite0").unwrap();
+        let instrument0 = objects.get("instrument0").unwrap();
+        let star0 = objects.get("star0").unwrap();
+        let star1 = objects.get("star1").unwrap();
+        //What -c switch_on=2 -c switch_off=4 -c calibrate=5 -c take_image=1.5 sets.
+        for (action, cost) in [("switch_on", 2.0), ("switch_off", 4.0), ("calibrate", 5.0), ("take_image", 1.5)].iter() {
+            assert!(state.config.costs.set(action, I40F24::from_num(*cost)));
+        }
+        assert!(!state.config.costs.set("turn_to", I40F24::from_num(1)));
+
+        for semantics in [Semantics::Numeric, Semantics::Strips].iter() {
+            state.set_semantics(*semantics);
+            let cost = |op: SatelliteOperator<SatelliteEnum>| op.cost(&state, &goal);
+            assert_eq!(cost(TurnTo(satellite0, star0, star1)), I40F24::from_num(7));
+            assert_eq!(cost(TurnTo(satellite0, star1, star0)), I40F24::from_num(3));
+            assert_eq!(cost(SwitchOn(instrument0, satellite0)), I40F24::from_num(2));
+            assert_eq!(cost(SwitchOff(instrument0, satellite0)), I40F24::from_num(4));
+            assert_eq!(cost(Calibrate(satellite0, instrument0, star0)), I40F24::from_num(5));
+            assert_eq!(cost(TakeImage(satellite0, star1, instrument0, objects.get("image0").unwrap())), I40F24::from_num(1.5));
+        }
+    }
+}
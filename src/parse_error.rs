@@ -0,0 +1,66 @@
+use std::{error, fmt, io};
+
+//Everything that can go wrong while turning a PDDL problem into a SatelliteState and SatelliteGoals.
+//Each variant carries the text of the offending predicate so that a bad file can be found quickly.
+#[derive(Debug)]
+pub enum SatelliteParseError {
+    //The problem could not be read, or is not well-formed PDDL.
+    Io(io::Error),
+    //An argument names an object missing from :objects.
+    UnknownObject { object: String, predicate: String },
+    WrongArity { predicate: String, expected: usize, found: usize },
+    //A fact or goal that is not part of the Satellite domain.
+    UnknownPredicate { predicate: String },
+    //A numeric problem that lacks a fluent the operators rely on, e.g. (slew_time star1 star0).
+    MissingFluent { fluent: String },
+    //An object used where the domain expects an object of a different type.
+    TypeMismatch { object: String, expected: String, found: String, predicate: String },
+    //An object declared with a type the Satellite domain does not have.
+    UnknownType { object: String, type_name: String },
+    //A :metric that is malformed or uses something other than the domain's fluents.
+    BadMetric { metric: String, reason: String },
+    //A serialized problem or plan whose contents do not fit together, e.g. an unknown action.
+    Malformed { item: String, reason: String },
+}
+
+impl fmt::Display for SatelliteParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use SatelliteParseError::*;
+        match self {
+            Io(e) => write!(f, "{}", e),
+            UnknownObject { object, predicate } => write!(f, "Unknown object \"{}\" in {}", object, predicate),
+            WrongArity { predicate, expected, found } => write!(f, "Expected {} arguments but found {} in {}", expected, found, predicate),
+            UnknownPredicate { predicate } => write!(f, "Unknown predicate {}", predicate),
+            MissingFluent { fluent } => write!(f, "Missing value for {}", fluent),
+            TypeMismatch { object, expected, found, predicate } => write!(f, "\"{}\" is a {} but {} needs a {}", object, found, predicate, expected),
+            UnknownType { object, type_name } => write!(f, "\"{}\" has unknown type {}", object, type_name),
+            BadMetric { metric, reason } => write!(f, "Cannot use the metric {}: {}", metric, reason),
+            Malformed { item, reason } => write!(f, "Cannot read {}: {}", item, reason),
+        }
+    }
+}
+
+impl error::Error for SatelliteParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SatelliteParseError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SatelliteParseError {
+    fn from(e: io::Error) -> Self {
+        SatelliteParseError::Io(e)
+    }
+}
+
+//Lets callers that work in io::Result keep using ? on the parser.
+impl From<SatelliteParseError> for io::Error {
+    fn from(e: SatelliteParseError) -> Self {
+        match e {
+            SatelliteParseError::Io(e) => e,
+            other => io::Error::new(io::ErrorKind::InvalidData, other.to_string()),
+        }
+    }
+}
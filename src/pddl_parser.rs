@@ -1,39 +1,71 @@
 use std::{fs, io};
-use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::process::exit;
 use std::rc::{self, Rc};
 use log::{debug, error, info, trace, warn};
 
 
 use pddl_problem_parser::{Predicate, PddlProblem};
-use crate::operators::{SatelliteEnum, SatelliteGoals, SatelliteState};
-use crate::operators::SatelliteEnum::{Direction, Instrument, Mode, Satellite};
+use crate::metric::PlanMetric;
+use crate::object_table::{ObjectTable, ObjectType};
+use crate::object_table::ObjectType::{Direction, Instrument, Mode, Satellite};
+use crate::parse_error::SatelliteParseError;
+use crate::parse_error::SatelliteParseError::{MissingFluent, TypeMismatch, UnknownObject, UnknownPredicate, UnknownType, WrongArity};
+use crate::operators::{SatelliteEnum, SatelliteGoals, SatelliteState, Semantics};
 use fixed::types::I40F24;
 
-pub fn make_satellite_problem_from(pddl_file: &str) -> io::Result<(SatelliteState, SatelliteGoals)> {
+//Returns the initial state and goals along with the table needed to turn their ids back into object names.
+pub fn make_satellite_problem_from(pddl_file: &str) -> Result<(SatelliteState, SatelliteGoals, ObjectTable), SatelliteParseError> {
     let contents = fs::read_to_string(pddl_file)?;
-    let parsed = pddl_problem_parser::PddlParser::parse(contents.as_str())?;
+    make_satellite_problem_from_str(contents.as_str())
+}
+
+//Reads the whole problem before parsing, so this works with stdin or a decompressing reader.
+pub fn make_satellite_problem_from_reader<R: Read>(mut reader: R) -> Result<(SatelliteState, SatelliteGoals, ObjectTable), SatelliteParseError> {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents)?;
+    make_satellite_problem_from_str(contents.as_str())
+}
+
+pub fn make_satellite_problem_from_str(contents: &str) -> Result<(SatelliteState, SatelliteGoals, ObjectTable), SatelliteParseError> {
+    let parsed = pddl_problem_parser::PddlParser::parse(contents)?;
 
-    let objects = enumerate_objects(&parsed);
+    let objects = enumerate_objects(&parsed)?;
 
     info!("objects {:?}", objects);
 
-    let satellite_state = extract_state(&parsed,&objects);
+    let satellite_state = extract_state(&parsed,&objects)?;
 
-    let goals = extract_goals(&parsed, &objects);
+    let metric = PlanMetric::from_problem(contents, &objects)?;
 
+    let goals = extract_goals(&parsed, &objects, metric)?;
 
-    return Ok((satellite_state, goals));
+
+    return Ok((satellite_state, goals, objects));
 }
 
-fn enumerate_objects(parsed: &PddlProblem) -> BTreeMap<String,I40F24> {
-    let mut objects: BTreeMap<String, I40F24> = BTreeMap::new();
-    for object in parsed.obj2type.keys() {
-        objects.insert(String::from(object), I40F24::from_num(objects.len()));
+//Every object gets the SatelliteEnum variant of its declared PDDL type.
+fn enumerate_objects(parsed: &PddlProblem) -> Result<ObjectTable, SatelliteParseError> {
+    let mut objects = ObjectTable::new();
+    for (object, type_name) in parsed.obj2type.iter() {
+        match ObjectType::from_pddl(type_name) {
+            Some(obj_type) => {objects.insert(object, obj_type);},
+            None => return Err(UnknownType { object: object.clone(), type_name: type_name.clone() }),
+        }
     }
-    return objects
+    return Ok(objects)
+}
+//The IPC files spell names both ways (slew_time but fuel-used) and not always in lower case, so
+//predicates and functions are matched by this form: lower case, with underscores for hyphens.
+pub fn canonical_name(name: &str) -> String {
+    name.to_lowercase().replace('-', "_")
 }
-fn extract_state(parsed: &PddlProblem, objects: &BTreeMap<String,I40F24>) -> SatelliteState {
+
+//The numeric fluents extract_state reads, by canonical name; any others in :init are skipped.
+pub const MODELED_FUNCTIONS: [&str; 6] = ["data_capacity", "fuel", "slew_time", "data", "fuel_used", "data_stored"];
+
+fn extract_state(parsed: &PddlProblem, objects: &ObjectTable) -> Result<SatelliteState, SatelliteParseError> {
 
     //These are everything that don't start with an equal
     let mut onboard: BTreeMap<SatelliteEnum, Vec<SatelliteEnum>>= BTreeMap::new();
@@ -42,133 +74,349 @@ fn extract_state(parsed: &PddlProblem, objects: &BTreeMap<String,I40F24>) -> Sat
     let mut power_avail:BTreeMap<SatelliteEnum, bool> = BTreeMap::new();
     let mut power_on: Vec<SatelliteEnum> = vec![];
     let mut calibrated: Vec<SatelliteEnum> = vec![];
-    let mut have_image: BTreeMap<SatelliteEnum, SatelliteEnum> = BTreeMap::new();
+    let mut have_image: BTreeSet<(SatelliteEnum, SatelliteEnum)> = BTreeSet::new();
     let mut calibration_target: BTreeMap<SatelliteEnum, SatelliteEnum> = BTreeMap::new();
 
     //These things begin with an equal.
     let mut data_capacity : BTreeMap<SatelliteEnum, I40F24> = BTreeMap::new();
     let mut satellite_data_stored: BTreeMap<(SatelliteEnum, SatelliteEnum), I40F24> = BTreeMap::new();
     let mut slew_time: BTreeMap<(SatelliteEnum, SatelliteEnum), I40F24> = BTreeMap::new();
-    let mut fuel_used = 0;
+    let mut fuel_used = I40F24::from_num(0);
     let mut fuel: BTreeMap<SatelliteEnum, I40F24>=  BTreeMap::new();
 
-    let mut total_data_stored = 0;
+    let mut total_data_stored = I40F24::from_num(0);
 
     for pred in parsed.bool_state.iter() {
-        if pred.get_tag() == "on_board" {
+        let tag = canonical_name(&pred.get_tag());
+        if tag == "on_board" {
             //map satellite -> vec<instrument>
-            let onboard_inserter = decode_onboard(&pred, &objects);
-            match onboard.get_mut(&Satellite(onboard_inserter.0)){
-                None => {onboard.insert(Satellite(onboard_inserter.0),vec![Instrument(onboard_inserter.1)]);},
-                Some(n) => n.push(Instrument(onboard_inserter.1)),
+            let (satellite, instrument) = decode_onboard(&pred, &objects)?;
+            match onboard.get_mut(&satellite){
+                None => {onboard.insert(satellite,vec![instrument]);},
+                Some(n) => n.push(instrument),
             };
-        } else if pred.get_tag() == "supports" {
-            let support_inserter = decode_supports(&pred, &objects);
-            match supports.get_mut(&Instrument(support_inserter.0)){
-                None => {supports.insert(Instrument(support_inserter.0), vec![Mode(support_inserter.1)]);},
-                Some(n) => n.push(Mode(support_inserter.1)),
+        } else if tag == "supports" {
+            let (instrument, mode) = decode_supports(&pred, &objects)?;
+            match supports.get_mut(&instrument){
+                None => {supports.insert(instrument, vec![mode]);},
+                Some(n) => n.push(mode),
             };
-        }else if pred.get_tag() == "pointing" {
-            let decoded_pointing = decode_pointing(&pred, &objects);
-            pointing.insert(Satellite(decoded_pointing.0), Direction(decoded_pointing.1));
-        }else if pred.get_tag() == "power_avail" {
-            let satellite = Satellite(*objects.get(pred.get_arg(0)).unwrap());
+        }else if tag == "pointing" {
+            let (satellite, direction) = decode_pointing(&pred, &objects)?;
+            pointing.insert(satellite, direction);
+        }else if tag == "power_avail" {
+            let satellite = decode_single(&pred, Satellite, &objects)?;
             power_avail.insert(satellite, true);
-        }else if pred.get_tag() == "power_on" {
-            power_on.push(Instrument(*objects.get(pred.get_arg(0)).unwrap()));
-        } else if pred.get_tag() == "calibrated" {
-            calibrated.push(Instrument(*objects.get(pred.get_arg(0)).unwrap()));
-        }else if pred.get_tag() == "have_image" {
-            let decoded_have_image = decode_have_image(&pred, &objects);
-            have_image.insert(Direction(decoded_have_image.0), Mode(decoded_have_image.1));
-        }else if pred.get_tag() == "calibration_target" {
-            let decoded_calibration_target = decode_calibration_target(&pred, &objects);
-            calibration_target.insert(Instrument(decoded_calibration_target.0), Direction(decoded_calibration_target.1));
+        }else if tag == "power_on" {
+            power_on.push(decode_single(&pred, Instrument, &objects)?);
+        } else if tag == "calibrated" {
+            calibrated.push(decode_single(&pred, Instrument, &objects)?);
+        }else if tag == "have_image" {
+            let (direction, mode) = decode_have_image(&pred, &objects)?;
+            have_image.insert((direction, mode));
+        }else if tag == "calibration_target" {
+            let (instrument, direction) = decode_calibration_target(&pred, &objects)?;
+            calibration_target.insert(instrument, direction);
+        }else {
+            return Err(UnknownPredicate { predicate: predicate_text(&pred) });
         }
     }
 
     //Parse things with an equals in them
     for (pred, value) in parsed.i40f24_state.iter(){
-        if pred.get_tag() == "data_capacity"{
-            let satellite = Satellite(obj_get(pred.get_arg(0), objects));
+        let tag = canonical_name(&pred.get_tag());
+        if tag == "data_capacity"{
+            let satellite = decode_single(&pred, Satellite, &objects)?;
             data_capacity.insert(satellite, value.to_num::<I40F24>());
-        }else if pred.get_tag() == "fuel"{
-            let satellite = Satellite(obj_get(pred.get_arg(0), objects));
+        }else if tag == "fuel"{
+            let satellite = decode_single(&pred, Satellite, &objects)?;
             fuel.insert(satellite,value.to_num::<I40F24>());
-        }else if pred.get_tag() == "slew_time" {
-            let position_a = Direction(obj_get(pred.get_arg(0), objects));
-            let position_b = Direction(obj_get(pred.get_arg(1), objects));
+        }else if tag == "slew_time" {
+            check_arity(&pred, 2)?;
+            let position_a = obj_get(&pred, 0, Direction, objects)?;
+            let position_b = obj_get(&pred, 1, Direction, objects)?;
             slew_time.insert((position_a, position_b), *value);
-        }else if pred.get_tag() == "data"{
-            let position = Direction(obj_get(pred.get_arg(0), objects));
-            let mode = Mode(obj_get(pred.get_arg(0), objects));
+        }else if tag == "data"{
+            //(data ?d ?m) is the size of the image of direction ?d taken in mode ?m.
+            check_arity(&pred, 2)?;
+            let position = obj_get(&pred, 0, Direction, objects)?;
+            let mode = obj_get(&pred, 1, Mode, objects)?;
             satellite_data_stored.insert((position,mode), *value);
-        }else if pred.get_tag() == "fuel_used"{
-            fuel_used = value.to_num();
+        }else if tag == "fuel_used"{
+            check_arity(&pred, 0)?;
+            fuel_used = *value;
+        }else if tag == "data_stored"{
+            check_arity(&pred, 0)?;
+            //The sizes in the data table are only stored once take_image is applied.
+            total_data_stored = *value;
+        }else {
+            //Unlike facts, functions we don't model are harmless, so they are skipped.
+            debug!("Ignoring numeric fluent {}", predicate_text(&pred));
         }
     }
-    for value in satellite_data_stored.values().into_iter(){
-        let value_as_u32 = value.to_num::<u32>();
-        total_data_stored+=value_as_u32;
+
+    //A problem with numeric fluents is a numeric problem, so turn_to needs fuel and slew times to work.
+    if !parsed.i40f24_state.is_empty() {
+        for satellite in objects.objects_of_type(Satellite) {
+            if !fuel.contains_key(&satellite) {
+                return Err(MissingFluent { fluent: format!("(fuel {})", objects.named(&satellite)) });
+            }
+            if !data_capacity.contains_key(&satellite) {
+                return Err(MissingFluent { fluent: format!("(data_capacity {})", objects.named(&satellite)) });
+            }
+        }
+        let directions = objects.objects_of_type(Direction);
+        for new_direction in directions.iter() {
+            for previous_direction in directions.iter().filter(|d| d != &new_direction) {
+                if !slew_time.contains_key(&(*new_direction, *previous_direction)) {
+                    return Err(MissingFluent { fluent: format!("(slew_time {} {})", objects.named(new_direction), objects.named(previous_direction)) });
+                }
+            }
+        }
     }
 
-    return SatelliteState::new(onboard,supports,pointing,power_avail,power_on,calibrated,have_image,calibration_target, data_capacity, I40F24::from_num(total_data_stored),satellite_data_stored,slew_time,I40F24::from_num(fuel_used), fuel);
+    let mut state = SatelliteState::new(onboard,supports,pointing,power_avail,power_on,calibrated,have_image,calibration_target, data_capacity, total_data_stored,satellite_data_stored,slew_time,fuel_used, fuel);
+    //Problems without any numeric fluents come from the STRIPS version of the domain.
+    if parsed.i40f24_state.is_empty() {
+        state.set_semantics(Semantics::Strips);
+    }
+    return Ok(state);
 }
 
-fn decode_onboard(p: &Predicate, objects: &BTreeMap<String,I40F24>) -> (I40F24, I40F24) {
-    let instrument = obj_get(p.get_arg(0), objects);
-    let satellite = obj_get(p.get_arg(1), objects);
+fn decode_onboard(p: &Predicate, objects: &ObjectTable) -> Result<(SatelliteEnum, SatelliteEnum), SatelliteParseError> {
+    check_arity(p, 2)?;
+    let instrument = obj_get(p, 0, Instrument, objects)?;
+    let satellite = obj_get(p, 1, Satellite, objects)?;
 
-    return (satellite, instrument);
+    return Ok((satellite, instrument));
 }
 
-fn decode_supports(p: &Predicate, objects: &BTreeMap<String,I40F24>) -> (I40F24, I40F24) {
+fn decode_supports(p: &Predicate, objects: &ObjectTable) -> Result<(SatelliteEnum, SatelliteEnum), SatelliteParseError> {
     //instrument modes
-    let instrument = obj_get(p.get_arg(0), objects);
-    let mode = obj_get(p.get_arg(1), objects);
+    check_arity(p, 2)?;
+    let instrument = obj_get(p, 0, Instrument, objects)?;
+    let mode = obj_get(p, 1, Mode, objects)?;
+
+    return Ok((instrument, mode));
 
-    return (instrument, mode);
+}
+
+fn decode_pointing(p: &Predicate, objects: &ObjectTable) -> Result<(SatelliteEnum, SatelliteEnum), SatelliteParseError> {
+    check_arity(p, 2)?;
+    let satellite = obj_get(p, 0, Satellite, objects)?;
+    let direction = obj_get(p, 1, Direction, objects)?;
+    Ok((satellite, direction))
+}
 
+fn decode_have_image(p: &Predicate, objects: &ObjectTable) -> Result<(SatelliteEnum, SatelliteEnum), SatelliteParseError> {
+    check_arity(p, 2)?;
+    let direction = obj_get(p, 0, Direction, objects)?;
+    let mode = obj_get(p, 1, Mode, objects)?;
+    Ok((direction, mode))
 }
 
-fn decode_pointing(p: &Predicate, objects: &BTreeMap<String,I40F24>) -> (I40F24, I40F24) {
-    let satellite = obj_get(p.get_arg(0), objects);
-    let direction = obj_get(p.get_arg(1), objects);
-    (satellite, direction)
+fn decode_calibration_target(p: &Predicate, objects: &ObjectTable) -> Result<(SatelliteEnum, SatelliteEnum), SatelliteParseError> {
+    check_arity(p, 2)?;
+    let instrument = obj_get(p, 0, Instrument, objects)?;
+    let direction = obj_get(p, 1, Direction, objects)?;
+    Ok((instrument, direction))
 }
 
-fn decode_have_image(p: &Predicate, objects: &BTreeMap<String,I40F24>) -> (I40F24, I40F24) {
-    let direction = obj_get(p.get_arg(0), objects);
-    let mode = obj_get(p.get_arg(1), objects);
-    (direction, mode)
+fn decode_single(p: &Predicate, expected: ObjectType, objects: &ObjectTable) -> Result<SatelliteEnum, SatelliteParseError> {
+    check_arity(p, 1)?;
+    obj_get(p, 0, expected, objects)
 }
 
-fn decode_calibration_target(p: &Predicate, objects: &BTreeMap<String,I40F24>) -> (I40F24, I40F24) {
-    let instrument = obj_get(p.get_arg(0), objects);
-    let direction = obj_get(p.get_arg(1), objects);
-    (instrument, direction)
+fn check_arity(p: &Predicate, expected: usize) -> Result<(), SatelliteParseError> {
+    if p.num_args() == expected {
+        Ok(())
+    } else {
+        Err(WrongArity { predicate: predicate_text(p), expected, found: p.num_args() })
+    }
+}
+
+//Looks up an argument, making sure its declared type is the one the predicate expects.
+fn obj_get(p: &Predicate, arg: usize, expected: ObjectType, objects: &ObjectTable) -> Result<SatelliteEnum, SatelliteParseError> {
+    let obj_name = p.get_arg(arg);
+    match objects.get(obj_name) {
+        Some(item) if ObjectType::of(&item) == expected => Ok(item),
+        Some(item) => Err(TypeMismatch { object: String::from(obj_name), expected: String::from(expected.pddl_name()), found: String::from(ObjectType::of(&item).pddl_name()), predicate: predicate_text(p) }),
+        None => Err(UnknownObject { object: String::from(obj_name), predicate: predicate_text(p) }),
+    }
 }
 
-fn obj_get(obj_name: &str, objects: &BTreeMap<String,I40F24>) -> I40F24 {
-    *(objects.get(obj_name).unwrap())
+//Rebuilds the PDDL text of a predicate for error messages, e.g. (pointing satellite0 star1).
+fn predicate_text(p: &Predicate) -> String {
+    let mut text = format!("({}", p.get_tag());
+    for arg in 0..p.num_args() {
+        text.push(' ');
+        text.push_str(p.get_arg(arg));
+    }
+    text.push(')');
+    text
 }
 
 
-fn extract_goals(parsed: &PddlProblem, objects: &BTreeMap<String,I40F24>) -> SatelliteGoals {
-    let mut have_image: BTreeMap<SatelliteEnum, SatelliteEnum> = BTreeMap::new();
+fn extract_goals(parsed: &PddlProblem, objects: &ObjectTable, metric: Option<PlanMetric>) -> Result<SatelliteGoals, SatelliteParseError> {
+    let mut have_image: BTreeSet<(SatelliteEnum, SatelliteEnum)> = BTreeSet::new();
     let mut pointing: BTreeMap<SatelliteEnum, SatelliteEnum> = BTreeMap::new();
 
-    let fuel_used = I40F24::from_num(0);
     for goal in parsed.goals.iter() {
-        if goal.get_tag() == "have_image" {
-            let decoded_have_image = decode_calibration_target(&goal, &objects);
-            have_image.insert(Direction(decoded_have_image.0), Mode(decoded_have_image.1));
-        }else if goal.get_tag() == "pointing"{
-            let satellite = obj_get(goal.get_arg(0), objects);
-            let direction = obj_get(goal.get_arg(1), objects);
-
-            pointing.insert(Satellite(satellite), Direction(direction));
+        let tag = canonical_name(&goal.get_tag());
+        if tag == "have_image" {
+            let (direction, mode) = decode_have_image(&goal, &objects)?;
+            have_image.insert((direction, mode));
+        }else if tag == "pointing"{
+            let (satellite, direction) = decode_pointing(&goal, &objects)?;
+
+            pointing.insert(satellite, direction);
+        }else {
+            return Err(UnknownPredicate { predicate: predicate_text(&goal) });
+        }
+    }
+    return Ok(SatelliteGoals::new(have_image, pointing, metric));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_sizes_are_keyed_by_direction_and_mode() {
+        let (state, _, objects) = make_satellite_problem_from("pfiles/pfile1").unwrap();
+        let size = |direction: &str, mode: &str| state.satellite_data_stored.get(&(objects.get(direction).unwrap(), objects.get(mode).unwrap())).copied();
+
+        assert_eq!(size("phenomenon3", "thermograph0"), Some(I40F24::from_num(197)));
+        assert_eq!(size("phenomenon3", "spectrograph2"), Some(I40F24::from_num(213)));
+        assert_eq!(size("star5", "image1"), Some(I40F24::from_num(31)));
+        assert_eq!(size("groundstation1", "image1"), None);
+        assert_eq!(state.satellite_data_stored.len(), 15);
+    }
+
+    const TINY_PROBLEM: &str = "(define (problem tiny) (:domain satellite)
+        (:objects satellite0 - satellite instrument0 - instrument image0 - mode star0 - direction star1 - direction)
+        (:init (on_board instrument0 satellite0) (supports instrument0 image0) (calibration_target instrument0 star0)
+               (power_avail satellite0) (pointing satellite0 star0))
+        (:goal (and (have_image star1 image0))))";
+
+    #[test]
+    fn parse_from_str_and_reader() {
+        let (state, goals, objects) = make_satellite_problem_from_str(TINY_PROBLEM).unwrap();
+        let satellite0 = objects.get("satellite0").unwrap();
+        assert_eq!(state.pointing.get(&satellite0), Some(&objects.get("star0").unwrap()));
+        assert_eq!(goals.have_image.len(), 1);
+
+        let (reader_state, reader_goals, _) = make_satellite_problem_from_reader(TINY_PROBLEM.as_bytes()).unwrap();
+        assert_eq!(reader_state, state);
+        assert_eq!(reader_goals, goals);
+    }
+
+    #[test]
+    fn undeclared_object_is_an_error() {
+        let problem = TINY_PROBLEM.replace("(pointing satellite0 star0)", "(pointing satellite0 star9)");
+        match make_satellite_problem_from_str(problem.as_str()) {
+            Err(SatelliteParseError::UnknownObject { object, predicate }) => {
+                assert_eq!(object, "star9");
+                assert_eq!(predicate, "(pointing satellite0 star9)");
+            }
+            other => panic!("Expected an unknown object, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn object_of_the_wrong_type_is_an_error() {
+        let problem = TINY_PROBLEM.replace("(pointing satellite0 star0)", "(pointing instrument0 star0)");
+        match make_satellite_problem_from_str(problem.as_str()) {
+            Err(SatelliteParseError::TypeMismatch { object, expected, found, .. }) => {
+                assert_eq!(object, "instrument0");
+                assert_eq!(expected, "satellite");
+                assert_eq!(found, "instrument");
+            }
+            other => panic!("Expected a type mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn wrong_number_of_arguments_is_an_error() {
+        let problem = TINY_PROBLEM.replace("(power_avail satellite0)", "(power_avail satellite0 star0)");
+        match make_satellite_problem_from_str(problem.as_str()) {
+            Err(SatelliteParseError::WrongArity { predicate, expected, found }) => {
+                assert_eq!(predicate, "(power_avail satellite0 star0)");
+                assert_eq!(expected, 1);
+                assert_eq!(found, 2);
+            }
+            other => panic!("Expected a wrong arity, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_predicate_is_an_error() {
+        let problem = TINY_PROBLEM.replace("(power_avail satellite0)", "(powered satellite0)");
+        match make_satellite_problem_from_str(problem.as_str()) {
+            Err(SatelliteParseError::UnknownPredicate { predicate }) => assert_eq!(predicate, "(powered satellite0)"),
+            other => panic!("Expected an unknown predicate, got {:?}", other),
+        }
+        let goal = TINY_PROBLEM.replace("(have_image star1 image0)", "(calibrated instrument0)");
+        match make_satellite_problem_from_str(goal.as_str()) {
+            Err(SatelliteParseError::UnknownPredicate { predicate }) => assert_eq!(predicate, "(calibrated instrument0)"),
+            other => panic!("Expected an unknown goal predicate, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_type_and_missing_fluent_are_errors() {
+        let problem = TINY_PROBLEM.replace("star1 - direction", "star1 - planet");
+        match make_satellite_problem_from_str(problem.as_str()) {
+            Err(SatelliteParseError::UnknownType { object, type_name }) => {
+                assert_eq!(object, "star1");
+                assert_eq!(type_name, "planet");
+            }
+            other => panic!("Expected an unknown type, got {:?}", other),
+        }
+        let numeric = TINY_PROBLEM.replace("(pointing satellite0 star0)", "(pointing satellite0 star0) (= (data_capacity satellite0) 100)");
+        match make_satellite_problem_from_str(numeric.as_str()) {
+            Err(SatelliteParseError::MissingFluent { fluent }) => assert_eq!(fluent, "(fuel satellite0)"),
+            other => panic!("Expected a missing fluent, got {:?}", other),
         }
     }
-    return SatelliteGoals::new(have_image, pointing,fuel_used);
+
+    #[test]
+    fn hyphens_and_case_do_not_change_the_problem() {
+        let (state, goals, _) = make_satellite_problem_from_str(TINY_PROBLEM).unwrap();
+        let respelled = TINY_PROBLEM.replace("on_board", "on-board").replace("calibration_target", "CALIBRATION-TARGET")
+            .replace("power_avail", "Power_Avail").replace("have_image", "have-image");
+        let (respelled_state, respelled_goals, _) = make_satellite_problem_from_str(respelled.as_str()).unwrap();
+        assert_eq!(respelled_state, state);
+        assert_eq!(respelled_goals, goals);
+
+        //The official numeric files mix the two: slew_time and data_capacity, but fuel-used and data-stored.
+        //pfiles/respelled/pfile1 is the same problem with every name in the other spelling.
+        let (state, goals, _) = make_satellite_problem_from("pfiles/pfile1").unwrap();
+        let (respelled_state, respelled_goals, _) = make_satellite_problem_from("pfiles/respelled/pfile1").unwrap();
+        assert_eq!(respelled_state, state);
+        assert_eq!(respelled_goals, goals);
+        //Both files start with nothing used, so check that a hyphenated fuel-used is read at all.
+        let official = fs::read_to_string("pfiles/pfile1").unwrap().replace("(= (fuel-used) 0)", "(= (fuel-used) 5)");
+        assert_eq!(make_satellite_problem_from_str(official.as_str()).unwrap().0.fuel_used, I40F24::from_num(5));
+    }
+
+    #[test]
+    fn semantics_come_from_the_problem() {
+        let (strips_state, _, objects) = make_satellite_problem_from_str(TINY_PROBLEM).unwrap();
+        let (numeric_state, numeric_goals, _) = make_satellite_problem_from("pfiles/pfile1").unwrap();
+        assert_eq!(strips_state.config.semantics, Semantics::Strips);
+        assert_eq!(numeric_state.config.semantics, Semantics::Numeric);
+        assert_eq!(numeric_goals.metric, Some(PlanMetric::fuel_used()));
+
+        let satellite0 = objects.get("satellite0").unwrap();
+        let star0 = objects.get("star0").unwrap();
+        let star1 = objects.get("star1").unwrap();
+        let mut strips = strips_state.clone();
+        assert!(strips.turn_to(&satellite0, &star1, &star0));
+
+        //The same problem read as numeric has no fuel, so the turn is impossible.
+        let mut numeric = strips_state.clone();
+        numeric.set_semantics(Semantics::Numeric);
+        assert!(!numeric.turn_to(&satellite0, &star1, &star0));
+    }
 }
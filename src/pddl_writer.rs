@@ -0,0 +1,142 @@
+use std::io;
+use std::io::Write;
+
+use crate::object_table::ObjectTable;
+use crate::operators::{SatelliteGoals, SatelliteState, Semantics};
+
+//Writes the state and goals as a problem of the IPC 2002 Satellite domain, laid out like the
+//IPC pfiles. Numeric facts are only written for numeric states, so that a STRIPS problem reads
+//back as a STRIPS problem.
+pub fn write_problem<W: Write>(name: &str, state: &SatelliteState, goals: &SatelliteGoals, objects: &ObjectTable, out: &mut W) -> io::Result<()> {
+    writeln!(out, "(define (problem {})", name)?;
+    writeln!(out, "(:domain satellite)")?;
+
+    writeln!(out, "(:objects")?;
+    for object in objects.names() {
+        writeln!(out, "\t{} - {}", object, objects.type_of(object).unwrap().pddl_name())?;
+    }
+    writeln!(out, ")")?;
+
+    writeln!(out, "(:init")?;
+    for (instrument, modes) in state.supports.iter() {
+        for mode in modes.iter() {
+            writeln!(out, "\t(supports {} {})", objects.named(instrument), objects.named(mode))?;
+        }
+    }
+    for (instrument, direction) in state.calibration_target.iter() {
+        writeln!(out, "\t(calibration_target {} {})", objects.named(instrument), objects.named(direction))?;
+    }
+    for (satellite, instruments) in state.onboard.iter() {
+        for instrument in instruments.iter() {
+            writeln!(out, "\t(on_board {} {})", objects.named(instrument), objects.named(satellite))?;
+        }
+    }
+    for (satellite, available) in state.power_avail.iter() {
+        if *available {
+            writeln!(out, "\t(power_avail {})", objects.named(satellite))?;
+        }
+    }
+    for instrument in state.power_on.iter() {
+        writeln!(out, "\t(power_on {})", objects.named(instrument))?;
+    }
+    for instrument in state.calibrated.iter() {
+        writeln!(out, "\t(calibrated {})", objects.named(instrument))?;
+    }
+    for (direction, mode) in state.have_image.iter() {
+        writeln!(out, "\t(have_image {} {})", objects.named(direction), objects.named(mode))?;
+    }
+    for (satellite, direction) in state.pointing.iter() {
+        writeln!(out, "\t(pointing {} {})", objects.named(satellite), objects.named(direction))?;
+    }
+    if state.config.semantics == Semantics::Numeric {
+        for (satellite, capacity) in state.data_capacity.iter() {
+            writeln!(out, "\t(= (data_capacity {}) {})", objects.named(satellite), capacity)?;
+        }
+        for (satellite, fuel) in state.fuel.iter() {
+            writeln!(out, "\t(= (fuel {}) {})", objects.named(satellite), fuel)?;
+        }
+        for ((direction, mode), size) in state.satellite_data_stored.iter() {
+            writeln!(out, "\t(= (data {} {}) {})", objects.named(direction), objects.named(mode), size)?;
+        }
+        for ((new_direction, previous_direction), time) in state.slew_time.iter() {
+            writeln!(out, "\t(= (slew_time {} {}) {})", objects.named(new_direction), objects.named(previous_direction), time)?;
+        }
+        writeln!(out, "\t(= (data-stored) {})", state.total_data_stored)?;
+        writeln!(out, "\t(= (fuel-used) {})", state.fuel_used)?;
+    }
+    writeln!(out, ")")?;
+
+    writeln!(out, "(:goal (and")?;
+    for (satellite, direction) in goals.pointing.iter() {
+        writeln!(out, "\t(pointing {} {})", objects.named(satellite), objects.named(direction))?;
+    }
+    for (direction, mode) in goals.have_image.iter() {
+        writeln!(out, "\t(have_image {} {})", objects.named(direction), objects.named(mode))?;
+    }
+    writeln!(out, "))")?;
+    if let Some(metric) = goals.metric.as_ref() {
+        writeln!(out, "{}", objects.named(metric))?;
+    }
+    writeln!(out)?;
+    writeln!(out, ")")
+}
+
+pub fn problem_to_string(name: &str, state: &SatelliteState, goals: &SatelliteGoals, objects: &ObjectTable) -> String {
+    let mut out: Vec<u8> = Vec::new();
+    write_problem(name, state, goals, objects, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhop::Operator;
+    use crate::domain::{check_problem, NUMERIC_DOMAIN};
+    use crate::pddl_parser::{make_satellite_problem_from, make_satellite_problem_from_str};
+    use crate::plan_io::parse_plan;
+
+    #[test]
+    fn written_problems_read_back_the_same() {
+        let (state, goals, objects) = make_satellite_problem_from("pfiles/pfile1").unwrap();
+        let written = problem_to_string("strips-sat-x-1", &state, &goals, &objects);
+        assert!(written.contains("\t(= (slew_time star0 groundstation1) 4.86)\n"));
+        assert!(written.contains("\t(= (data-stored) 0)\n"));
+        assert!(written.contains("\t(= (fuel-used) 0)\n"));
+        assert!(written.ends_with("(:metric minimize (fuel-used))\n\n)\n"));
+        let (read_state, read_goals, read_objects) = make_satellite_problem_from_str(written.as_str()).unwrap();
+        assert_eq!(read_state, state);
+        assert_eq!(read_goals, goals);
+        assert_eq!(read_objects, objects);
+    }
+
+    #[test]
+    fn partly_executed_problems_keep_their_progress() {
+        let (mut state, goals, objects) = make_satellite_problem_from("pfiles/pfile1").unwrap();
+        for step in parse_plan("(switch_on instrument0 satellite0)\n(turn_to satellite0 groundstation2 phenomenon6)", &objects).unwrap() {
+            assert!(step.attempt_update(&mut state));
+        }
+        let written = problem_to_string("partial", &state, &goals, &objects);
+        assert!(written.contains("\t(power_on instrument0)\n"));
+        assert!(!written.contains("(power_avail satellite0)"));
+        //power_avail satellite0 is now false, which PDDL says by leaving it out.
+        let (read_state, _, _) = make_satellite_problem_from_str(written.as_str()).unwrap();
+        assert_eq!(read_state.power_on, state.power_on);
+        assert_eq!(read_state.pointing, state.pointing);
+        assert_eq!(read_state.fuel, state.fuel);
+        assert_eq!(read_state.fuel_used, state.fuel_used);
+        assert_ne!(read_state.power_avail.get(&objects.get("satellite0").unwrap()), Some(&true));
+    }
+
+    #[test]
+    fn written_problems_match_the_numeric_domain() {
+        let (state, goals, objects) = make_satellite_problem_from("pfiles/pfile1").unwrap();
+        let written = problem_to_string("strips-sat-x-1", &state, &goals, &objects);
+        assert_eq!(check_problem(written.as_str()).unwrap(), vec![]);
+        //check_problem ignores hyphens versus underscores, but other planners and VAL do not.
+        for line in written.lines().filter(|line| line.starts_with("\t(= (")) {
+            let function = line["\t(= (".len()..].split(|c| c == ' ' || c == ')').next().unwrap();
+            assert!(NUMERIC_DOMAIN.contains(format!("({} ", function).as_str()) || NUMERIC_DOMAIN.contains(format!("({})", function).as_str()),
+                    "{} is not declared by the domain", function);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+
+use crate::object_table::{ObjectTable, ObjectType};
+use crate::operators::SatelliteEnum;
+use crate::operators::SatelliteOperator;
+use crate::operators::SatelliteOperator::{Calibrate, SwitchOff, SwitchOn, TakeImage, TurnTo};
+use crate::pddl_parser::canonical_name;
+
+//Writes the plan one action per line, e.g. (turn_to satellite0 star1 star0), which is the
+//format VAL and the other IPC tools expect.
+pub fn write_plan<W: Write>(plan: &[SatelliteOperator<SatelliteEnum>], objects: &ObjectTable, out: &mut W) -> io::Result<()> {
+    for step in plan.iter() {
+        writeln!(out, "({})", objects.named(step))?;
+    }
+    Ok(())
+}
+
+pub fn plan_to_string(plan: &[SatelliteOperator<SatelliteEnum>], objects: &ObjectTable) -> String {
+    let mut out: Vec<u8> = Vec::new();
+    write_plan(plan, objects, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+pub fn read_plan<R: Read>(mut reader: R, objects: &ObjectTable) -> io::Result<Vec<SatelliteOperator<SatelliteEnum>>> {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents)?;
+    parse_plan(contents.as_str(), objects)
+}
+
+//Reads plans written by write_plan as well as the usual IPC variants: ; comments,
+//a leading "0:" time stamp and a trailing "[1]" duration are all ignored.
+pub fn parse_plan(contents: &str, objects: &ObjectTable) -> io::Result<Vec<SatelliteOperator<SatelliteEnum>>> {
+    let mut plan = vec![];
+    for (line_num, line) in contents.lines().enumerate() {
+        let line = strip_decorations(line);
+        if line.is_empty() {
+            continue;
+        }
+        plan.push(parse_step(line, objects).map_err(|msg| plan_error(line_num + 1, msg))?);
+    }
+    Ok(plan)
+}
+
+fn strip_decorations(line: &str) -> &str {
+    let mut line = match line.find(';') {
+        Some(start) => &line[..start],
+        None => line,
+    };
+    if let Some(open) = line.find('(') {
+        if line[..open].trim_end().ends_with(':') {
+            line = &line[open..];
+        }
+    }
+    if let Some(close) = line.rfind(')') {
+        line = &line[..close + 1];
+    }
+    line.trim()
+}
+
+pub(crate) fn parse_step(line: &str, objects: &ObjectTable) -> Result<SatelliteOperator<SatelliteEnum>, String> {
+    if !line.starts_with('(') || !line.ends_with(')') {
+        return Err(format!("expected a parenthesized action, found \"{}\"", line));
+    }
+    let words: Vec<&str> = line[1..line.len() - 1].split_whitespace().collect();
+    if words.is_empty() {
+        return Err(String::from("empty action"));
+    }
+    let action = canonical_name(words[0]);
+    let args = &words[1..];
+    let expected_args = match action.as_str() {
+        "turn_to" | "calibrate" => 3,
+        "switch_on" | "switch_off" => 2,
+        "take_image" => 4,
+        _ => return Err(format!("unknown action \"{}\"", words[0])),
+    };
+    if args.len() != expected_args {
+        return Err(format!("{} takes {} arguments but was given {}", action, expected_args, args.len()));
+    }
+    let obj = |i: usize, expected: ObjectType| match objects.get(args[i]) {
+        Some(item) if ObjectType::of(&item) == expected => Ok(item),
+        Some(_) => Err(format!("\"{}\" is not a {}", args[i], expected.pddl_name())),
+        None => Err(format!("unknown object \"{}\"", args[i])),
+    };
+    use ObjectType::*;
+    Ok(match action.as_str() {
+        "turn_to" => TurnTo(obj(0, Satellite)?, obj(1, Direction)?, obj(2, Direction)?),
+        "switch_on" => SwitchOn(obj(0, Instrument)?, obj(1, Satellite)?),
+        "switch_off" => SwitchOff(obj(0, Instrument)?, obj(1, Satellite)?),
+        "calibrate" => Calibrate(obj(0, Satellite)?, obj(1, Instrument)?, obj(2, Direction)?),
+        _ => TakeImage(obj(0, Satellite)?, obj(1, Direction)?, obj(2, Instrument)?, obj(3, Mode)?),
+    })
+}
+
+fn plan_error(line_num: usize, msg: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, format!("Plan line {}: {}", line_num, msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut objects = ObjectTable::new();
+        let satellite = objects.insert("satellite0", ObjectType::Satellite);
+        let instrument = objects.insert("instrument0", ObjectType::Instrument);
+        let mode = objects.insert("thermograph0", ObjectType::Mode);
+        let star = objects.insert("star0", ObjectType::Direction);
+        let station = objects.insert("groundstation1", ObjectType::Direction);
+        let plan = vec![SwitchOn(instrument, satellite),
+                        TurnTo(satellite, station, star),
+                        Calibrate(satellite, instrument, station),
+                        TurnTo(satellite, star, station),
+                        TakeImage(satellite, star, instrument, mode),
+                        SwitchOff(instrument, satellite)];
+
+        let written = plan_to_string(&plan, &objects);
+        assert!(written.starts_with("(switch_on instrument0 satellite0)\n(turn_to satellite0 groundstation1 star0)\n"));
+        assert_eq!(parse_plan(written.as_str(), &objects).unwrap(), plan);
+    }
+
+    #[test]
+    fn ipc_decorations() {
+        let mut objects = ObjectTable::new();
+        let instrument = objects.insert("instrument0", ObjectType::Instrument);
+        let satellite = objects.insert("satellite0", ObjectType::Satellite);
+        let plan = parse_plan("; a comment\n0: (SWITCH_ON instrument0 satellite0) [1]\n\n", &objects).unwrap();
+        assert_eq!(plan, vec![SwitchOn(instrument, satellite)]);
+        assert!(parse_plan("(switch_on instrument0)", &objects).is_err());
+        assert!(parse_plan("(switch_on satellite0 instrument0)", &objects).is_err());
+        assert!(parse_plan("(switch_on instrument1 satellite0)", &objects).is_err());
+    }
+}
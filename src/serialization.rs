@@ -0,0 +1,416 @@
+//Serde support, behind the serde feature. Objects are written by their PDDL names rather than
+//their ids, so the JSON stays stable when a problem lists its objects in another order, and
+//every I40F24 is written as exact decimal text.
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use fixed::types::I40F24;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::methods::{SatelliteMethod, SatelliteStatus};
+use crate::metric::PlanMetric;
+use crate::object_table::{ObjectTable, ObjectType};
+use crate::operators::{DomainConfig, GoalOrdering, Heuristic, OperatorCosts, SatelliteEnum, SatelliteGoals, SatelliteOperator, SatelliteState, Semantics};
+use crate::parse_error::SatelliteParseError;
+use crate::plan_io::parse_step;
+
+//An I40F24 written as a string such as "12.5", which parses back to exactly the same value.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub struct Exact(pub I40F24);
+
+impl Serialize for Exact {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Exact {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        I40F24::from_str(text.as_str())
+            .map(Exact)
+            .map_err(|_| <D::Error as serde::de::Error>::custom(format!("{} is not a fixed-point number", text)))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NamedObject {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub object_type: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NamedImage {
+    pub direction: String,
+    pub mode: String,
+}
+
+//The size of an image, from (data ?d ?m).
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NamedData {
+    pub direction: String,
+    pub mode: String,
+    pub size: Exact,
+}
+
+//The time to turn from previous_direction to new_direction, from (slew_time ?new ?previous).
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NamedSlew {
+    pub new_direction: String,
+    pub previous_direction: String,
+    pub time: Exact,
+}
+
+//SatelliteStatus::NotDone; Done is written as null.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NamedStatus {
+    pub identifier: u32,
+    pub satellite: String,
+    pub instrument: String,
+    pub mode: String,
+    pub new_direction: String,
+    pub previous_direction: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NamedConfig {
+    pub semantics: String,
+    //action -> cost, for the actions other than turn_to
+    pub costs: BTreeMap<String, Exact>,
+    pub orderings: Vec<String>,
+    pub branching_limit: Option<usize>,
+    pub heuristic: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NamedState {
+    pub onboard: BTreeMap<String, Vec<String>>,
+    pub supports: BTreeMap<String, Vec<String>>,
+    pub pointing: BTreeMap<String, String>,
+    pub power_avail: BTreeMap<String, bool>,
+    pub power_on: Vec<String>,
+    pub calibrated: Vec<String>,
+    pub have_image: Vec<NamedImage>,
+    pub calibration_target: BTreeMap<String, String>,
+    pub data_capacity: BTreeMap<String, Exact>,
+    pub data_stored: Exact,
+    pub data: Vec<NamedData>,
+    pub slew_time: Vec<NamedSlew>,
+    pub fuel_used: Exact,
+    pub fuel: BTreeMap<String, Exact>,
+    pub status: Option<NamedStatus>,
+    pub config: NamedConfig,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NamedGoals {
+    pub have_image: Vec<NamedImage>,
+    pub pointing: BTreeMap<String, String>,
+    //In PDDL, e.g. (:metric minimize (fuel-used))
+    pub metric: Option<String>,
+}
+
+//Everything make_satellite_problem_from returns, in one serializable value.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NamedProblem {
+    //In id order, so that reading them back assigns the same ids.
+    pub objects: Vec<NamedObject>,
+    pub state: NamedState,
+    pub goals: NamedGoals,
+}
+
+//An operator or method with its arguments, e.g. {"name": "turn_to", "args": ["satellite0", "star1", "star0"]}
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NamedTask {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl NamedProblem {
+    pub fn from_problem(state: &SatelliteState, goals: &SatelliteGoals, objects: &ObjectTable) -> Self {
+        let named_objects = objects.names()
+            .map(|name| NamedObject { name: String::from(name), object_type: String::from(objects.type_of(name).unwrap().pddl_name()) })
+            .collect();
+        NamedProblem { objects: named_objects, state: NamedState::from_state(state, objects), goals: NamedGoals::from_goals(goals, objects) }
+    }
+
+    pub fn to_problem(&self) -> Result<(SatelliteState, SatelliteGoals, ObjectTable), SatelliteParseError> {
+        let mut objects = ObjectTable::new();
+        for object in self.objects.iter() {
+            match ObjectType::from_pddl(object.object_type.as_str()) {
+                Some(obj_type) => objects.insert(object.name.as_str(), obj_type),
+                None => return Err(SatelliteParseError::UnknownType { object: object.name.clone(), type_name: object.object_type.clone() }),
+            };
+        }
+        let state = self.state.to_state(&objects)?;
+        let goals = self.goals.to_goals(&objects)?;
+        Ok((state, goals, objects))
+    }
+}
+
+impl NamedState {
+    pub fn from_state(state: &SatelliteState, objects: &ObjectTable) -> Self {
+        let to_name = |item: &SatelliteEnum| name(item, objects);
+        let names = |items: &Vec<SatelliteEnum>| items.iter().map(to_name).collect::<Vec<String>>();
+        let exact_map = |map: &BTreeMap<SatelliteEnum, I40F24>| -> BTreeMap<String, Exact> { map.iter().map(|(k, v)| (to_name(k), Exact(*v))).collect() };
+        let status = match &state.status {
+            SatelliteStatus::Done => None,
+            SatelliteStatus::NotDone(identifier, satellite, instrument, mode, new_direction, previous_direction) => Some(NamedStatus {
+                identifier: *identifier, satellite: to_name(satellite), instrument: to_name(instrument), mode: to_name(mode),
+                new_direction: to_name(new_direction), previous_direction: to_name(previous_direction) }),
+        };
+        NamedState {
+            onboard: state.onboard.iter().map(|(k, v)| (to_name(k), names(v))).collect(),
+            supports: state.supports.iter().map(|(k, v)| (to_name(k), names(v))).collect(),
+            pointing: state.pointing.iter().map(|(k, v)| (to_name(k), to_name(v))).collect(),
+            power_avail: state.power_avail.iter().map(|(k, v)| (to_name(k), *v)).collect(),
+            power_on: names(&state.power_on),
+            calibrated: names(&state.calibrated),
+            have_image: named_images(&state.have_image, objects),
+            calibration_target: state.calibration_target.iter().map(|(k, v)| (to_name(k), to_name(v))).collect(),
+            data_capacity: exact_map(&state.data_capacity),
+            data_stored: Exact(state.total_data_stored),
+            data: state.satellite_data_stored.iter()
+                .map(|((direction, mode), size)| NamedData { direction: to_name(direction), mode: to_name(mode), size: Exact(*size) })
+                .collect(),
+            slew_time: state.slew_time.iter()
+                .map(|((new_direction, previous_direction), time)| NamedSlew { new_direction: to_name(new_direction), previous_direction: to_name(previous_direction), time: Exact(*time) })
+                .collect(),
+            fuel_used: Exact(state.fuel_used),
+            fuel: exact_map(&state.fuel),
+            status,
+            config: NamedConfig::from_config(&state.config),
+        }
+    }
+
+    pub fn to_state(&self, objects: &ObjectTable) -> Result<SatelliteState, SatelliteParseError> {
+        use ObjectType::*;
+        let list = |items: &Vec<String>, obj_type, item: &str| items.iter().map(|n| lookup(objects, n, obj_type, item)).collect::<Result<Vec<SatelliteEnum>, SatelliteParseError>>();
+        let mut onboard = BTreeMap::new();
+        for (satellite, instruments) in self.onboard.iter() {
+            onboard.insert(lookup(objects, satellite, Satellite, "onboard")?, list(instruments, Instrument, "onboard")?);
+        }
+        let mut supports = BTreeMap::new();
+        for (instrument, modes) in self.supports.iter() {
+            supports.insert(lookup(objects, instrument, Instrument, "supports")?, list(modes, Mode, "supports")?);
+        }
+        let pointing = pairs(&self.pointing, objects, Satellite, Direction, "pointing")?;
+        let mut power_avail = BTreeMap::new();
+        for (satellite, available) in self.power_avail.iter() {
+            power_avail.insert(lookup(objects, satellite, Satellite, "power_avail")?, *available);
+        }
+        let calibration_target = pairs(&self.calibration_target, objects, Instrument, Direction, "calibration_target")?;
+        let mut satellite_data_stored = BTreeMap::new();
+        for data in self.data.iter() {
+            satellite_data_stored.insert((lookup(objects, &data.direction, Direction, "data")?, lookup(objects, &data.mode, Mode, "data")?), data.size.0);
+        }
+        let mut slew_time = BTreeMap::new();
+        for slew in self.slew_time.iter() {
+            slew_time.insert((lookup(objects, &slew.new_direction, Direction, "slew_time")?, lookup(objects, &slew.previous_direction, Direction, "slew_time")?), slew.time.0);
+        }
+        let mut state = SatelliteState::new(onboard, supports, pointing, power_avail,
+                                            list(&self.power_on, Instrument, "power_on")?,
+                                            list(&self.calibrated, Instrument, "calibrated")?,
+                                            images(&self.have_image, objects)?,
+                                            calibration_target,
+                                            exact_values(&self.data_capacity, objects, "data_capacity")?,
+                                            self.data_stored.0, satellite_data_stored, slew_time, self.fuel_used.0,
+                                            exact_values(&self.fuel, objects, "fuel")?);
+        if let Some(status) = self.status.as_ref() {
+            state.status = SatelliteStatus::NotDone(status.identifier,
+                                                    lookup(objects, &status.satellite, Satellite, "status")?,
+                                                    lookup(objects, &status.instrument, Instrument, "status")?,
+                                                    lookup(objects, &status.mode, Mode, "status")?,
+                                                    lookup(objects, &status.new_direction, Direction, "status")?,
+                                                    lookup(objects, &status.previous_direction, Direction, "status")?);
+        }
+        state.config = self.config.to_config()?;
+        Ok(state)
+    }
+}
+
+impl NamedConfig {
+    pub fn from_config(config: &DomainConfig) -> Self {
+        let costs = &config.costs;
+        NamedConfig {
+            semantics: config.semantics.to_string(),
+            costs: vec![("switch_on", costs.switch_on), ("switch_off", costs.switch_off), ("calibrate", costs.calibrate), ("take_image", costs.take_image)].into_iter()
+                .map(|(action, cost)| (String::from(action), Exact(cost)))
+                .collect(),
+            orderings: config.orderings.iter().map(|ordering| ordering.to_string()).collect(),
+            branching_limit: config.branching_limit,
+            heuristic: config.heuristic.to_string(),
+        }
+    }
+
+    pub fn to_config(&self) -> Result<DomainConfig, SatelliteParseError> {
+        let mut config = DomainConfig::new(Semantics::from_str(self.semantics.as_str()).map_err(|_| malformed("semantics", &self.semantics))?);
+        let mut costs = OperatorCosts::default();
+        for (action, cost) in self.costs.iter() {
+            if !costs.set(action.as_str(), cost.0) {
+                return Err(malformed("costs", action));
+            }
+        }
+        config.costs = costs;
+        config.orderings = self.orderings.iter()
+            .map(|ordering| GoalOrdering::from_str(ordering.as_str()).map_err(|_| malformed("orderings", ordering)))
+            .collect::<Result<Vec<GoalOrdering>, SatelliteParseError>>()?;
+        config.branching_limit = self.branching_limit;
+        config.heuristic = Heuristic::from_str(self.heuristic.as_str()).map_err(|_| malformed("heuristic", &self.heuristic))?;
+        Ok(config)
+    }
+}
+
+impl NamedGoals {
+    pub fn from_goals(goals: &SatelliteGoals, objects: &ObjectTable) -> Self {
+        NamedGoals {
+            have_image: named_images(&goals.have_image, objects),
+            pointing: goals.pointing.iter().map(|(k, v)| (name(k, objects), name(v, objects))).collect(),
+            metric: goals.metric.as_ref().map(|metric| objects.named(metric).to_string()),
+        }
+    }
+
+    pub fn to_goals(&self, objects: &ObjectTable) -> Result<SatelliteGoals, SatelliteParseError> {
+        let metric = match self.metric.as_ref() {
+            Some(metric) => PlanMetric::from_problem(metric.as_str(), objects)?,
+            None => None,
+        };
+        Ok(SatelliteGoals::new(images(&self.have_image, objects)?,
+                               pairs(&self.pointing, objects, ObjectType::Satellite, ObjectType::Direction, "pointing")?,
+                               metric))
+    }
+}
+
+impl NamedTask {
+    pub fn from_operator(step: &SatelliteOperator<SatelliteEnum>, objects: &ObjectTable) -> Self {
+        NamedTask { name: String::from(step.action_name()), args: step.args().iter().map(|arg| name(arg, objects)).collect() }
+    }
+
+    pub fn to_operator(&self, objects: &ObjectTable) -> Result<SatelliteOperator<SatelliteEnum>, SatelliteParseError> {
+        parse_step(format!("({} {})", self.name, self.args.join(" ")).as_str(), objects)
+            .map_err(|reason| SatelliteParseError::Malformed { item: String::from("plan step"), reason })
+    }
+
+    pub fn from_method(method: &SatelliteMethod, objects: &ObjectTable) -> Self {
+        let (method_name, args) = match method {
+            SatelliteMethod::ScheduleAll => ("schedule_all", vec![]),
+            SatelliteMethod::ScheduleOne(satellite, instrument, mode, new_direction, previous_direction) =>
+                ("schedule_one", vec![*satellite, *instrument, *mode, *new_direction, *previous_direction]),
+            SatelliteMethod::Switching(satellite, instrument) => ("switching", vec![*satellite, *instrument]),
+        };
+        NamedTask { name: String::from(method_name), args: args.iter().map(|arg| name(arg, objects)).collect() }
+    }
+
+    pub fn to_method(&self, objects: &ObjectTable) -> Result<SatelliteMethod, SatelliteParseError> {
+        use ObjectType::*;
+        let expected: &[ObjectType] = match self.name.as_str() {
+            "schedule_all" => &[],
+            "schedule_one" => &[Satellite, Instrument, Mode, Direction, Direction],
+            "switching" => &[Satellite, Instrument],
+            _ => return Err(malformed("method", &self.name)),
+        };
+        if self.args.len() != expected.len() {
+            return Err(SatelliteParseError::WrongArity { predicate: self.name.clone(), expected: expected.len(), found: self.args.len() });
+        }
+        let args = self.args.iter().zip(expected.iter())
+            .map(|(arg, obj_type)| lookup(objects, arg, *obj_type, self.name.as_str()))
+            .collect::<Result<Vec<SatelliteEnum>, SatelliteParseError>>()?;
+        Ok(match self.name.as_str() {
+            "schedule_all" => SatelliteMethod::ScheduleAll,
+            "schedule_one" => SatelliteMethod::ScheduleOne(args[0], args[1], args[2], args[3], args[4]),
+            _ => SatelliteMethod::Switching(args[0], args[1]),
+        })
+    }
+}
+
+pub fn plan_to_named(plan: &[SatelliteOperator<SatelliteEnum>], objects: &ObjectTable) -> Vec<NamedTask> {
+    plan.iter().map(|step| NamedTask::from_operator(step, objects)).collect()
+}
+
+pub fn plan_from_named(plan: &[NamedTask], objects: &ObjectTable) -> Result<Vec<SatelliteOperator<SatelliteEnum>>, SatelliteParseError> {
+    plan.iter().map(|step| step.to_operator(objects)).collect()
+}
+
+fn name(item: &SatelliteEnum, objects: &ObjectTable) -> String {
+    objects.named(item).to_string()
+}
+
+fn lookup(objects: &ObjectTable, object: &str, expected: ObjectType, item: &str) -> Result<SatelliteEnum, SatelliteParseError> {
+    match objects.get(object) {
+        Some(found) if ObjectType::of(&found) == expected => Ok(found),
+        Some(found) => Err(SatelliteParseError::TypeMismatch { object: String::from(object), expected: String::from(expected.pddl_name()),
+                                                               found: String::from(ObjectType::of(&found).pddl_name()), predicate: String::from(item) }),
+        None => Err(SatelliteParseError::UnknownObject { object: String::from(object), predicate: String::from(item) }),
+    }
+}
+
+fn pairs(map: &BTreeMap<String, String>, objects: &ObjectTable, key_type: ObjectType, value_type: ObjectType, item: &str) -> Result<BTreeMap<SatelliteEnum, SatelliteEnum>, SatelliteParseError> {
+    map.iter()
+        .map(|(k, v)| Ok((lookup(objects, k, key_type, item)?, lookup(objects, v, value_type, item)?)))
+        .collect()
+}
+
+fn exact_values(map: &BTreeMap<String, Exact>, objects: &ObjectTable, item: &str) -> Result<BTreeMap<SatelliteEnum, I40F24>, SatelliteParseError> {
+    map.iter()
+        .map(|(satellite, value)| Ok((lookup(objects, satellite, ObjectType::Satellite, item)?, value.0)))
+        .collect()
+}
+
+fn named_images(images: &BTreeSet<(SatelliteEnum, SatelliteEnum)>, objects: &ObjectTable) -> Vec<NamedImage> {
+    images.iter()
+        .map(|(direction, mode)| NamedImage { direction: name(direction, objects), mode: name(mode, objects) })
+        .collect()
+}
+
+fn images(images: &[NamedImage], objects: &ObjectTable) -> Result<BTreeSet<(SatelliteEnum, SatelliteEnum)>, SatelliteParseError> {
+    images.iter()
+        .map(|image| Ok((lookup(objects, &image.direction, ObjectType::Direction, "have_image")?, lookup(objects, &image.mode, ObjectType::Mode, "have_image")?)))
+        .collect()
+}
+
+fn malformed(item: &str, value: &str) -> SatelliteParseError {
+    SatelliteParseError::Malformed { item: String::from(item), reason: format!("unknown value {}", value) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pddl_parser::make_satellite_problem_from;
+    use crate::plan_io::parse_plan;
+
+    #[test]
+    fn problem_round_trips_through_json() {
+        let (state, goals, objects) = make_satellite_problem_from("pfiles/pfile1").unwrap();
+        let json = serde_json::to_string_pretty(&NamedProblem::from_problem(&state, &goals, &objects)).unwrap();
+        assert!(json.contains("\"fuel_used\": \"0\""));
+        assert!(json.contains("\"metric\": \"(:metric minimize (fuel-used))\""));
+
+        let named: NamedProblem = serde_json::from_str(json.as_str()).unwrap();
+        let (read_state, read_goals, read_objects) = named.to_problem().unwrap();
+        assert_eq!(read_state, state);
+        assert_eq!(read_goals, goals);
+        assert_eq!(read_objects, objects);
+    }
+
+    #[test]
+    fn fractions_are_exact() {
+        let value = I40F24::from_num(0.1) + I40F24::from_bits(1);
+        let json = serde_json::to_string(&Exact(value)).unwrap();
+        let read: Exact = serde_json::from_str(json.as_str()).unwrap();
+        assert_eq!(read.0, value);
+    }
+
+    #[test]
+    fn plans_use_object_names() {
+        let (_, _, objects) = make_satellite_problem_from("pfiles/pfile1").unwrap();
+        let plan = parse_plan("(switch_on instrument0 satellite0)\n(turn_to satellite0 groundstation2 star0)", &objects).unwrap();
+        let json = serde_json::to_string(&plan_to_named(&plan, &objects)).unwrap();
+        assert_eq!(json, "[{\"name\":\"switch_on\",\"args\":[\"instrument0\",\"satellite0\"]},{\"name\":\"turn_to\",\"args\":[\"satellite0\",\"groundstation2\",\"star0\"]}]");
+        let named: Vec<NamedTask> = serde_json::from_str(json.as_str()).unwrap();
+        assert_eq!(plan_from_named(&named, &objects).unwrap(), plan);
+
+        let method = SatelliteMethod::Switching(objects.get("satellite0").unwrap(), objects.get("instrument0").unwrap());
+        assert_eq!(NamedTask::from_method(&method, &objects).to_method(&objects).unwrap(), method);
+    }
+}
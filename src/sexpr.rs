@@ -0,0 +1,81 @@
+use std::fmt;
+
+//Just enough of a PDDL reader for the parts of a problem that pddl_problem_parser does not
+//give back, such as the :metric, and for reading domain files.
+pub(crate) enum SExpr {
+    Atom(String),
+    List(Vec<SExpr>),
+}
+
+impl SExpr {
+    //The items of a list whose first item is the given atom, e.g. the facts of (:init ...)
+    pub(crate) fn tagged(&self, tag: &str) -> Option<&[SExpr]> {
+        match self {
+            SExpr::List(items) if items.first().and_then(atom).map_or(false, |a| a.eq_ignore_ascii_case(tag)) => Some(&items[1..]),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SExpr::Atom(a) => write!(f, "{}", a),
+            SExpr::List(items) => {
+                write!(f, "(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+pub(crate) fn atom(sexpr: &SExpr) -> Option<&str> {
+    match sexpr {
+        SExpr::Atom(a) => Some(a.as_str()),
+        SExpr::List(_) => None,
+    }
+}
+
+//Splits the text into parentheses and atoms, dropping ; comments.
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| match line.find(';') {
+            Some(start) => &line[..start],
+            None => line,
+        })
+        .flat_map(|line| line.replace('(', " ( ").replace(')', " ) ")
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<String>>())
+        .collect()
+}
+
+//Reads one expression from the front of tokens, returning it with the unread tokens.
+pub(crate) fn read_sexpr(tokens: &[String]) -> Result<(SExpr, &[String]), String> {
+    match tokens.split_first() {
+        None => Err(String::from("unexpected end of input")),
+        Some((first, rest)) if first == "(" => {
+            let mut items = vec![];
+            let mut rest = rest;
+            loop {
+                match rest.split_first() {
+                    None => return Err(String::from("missing )")),
+                    Some((close, after)) if close == ")" => return Ok((SExpr::List(items), after)),
+                    _ => {
+                        let (item, after) = read_sexpr(rest)?;
+                        items.push(item);
+                        rest = after;
+                    }
+                }
+            }
+        }
+        Some((first, _)) if first == ")" => Err(String::from("unexpected )")),
+        Some((first, rest)) => Ok((SExpr::Atom(first.clone()), rest)),
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+use std::io::Write;
+
+use anyhop::Operator;
+use fixed::types::I40F24;
+
+use crate::object_table::{NamedDisplay, ObjectTable};
+use crate::operators::{SatelliteEnum, SatelliteOperator, SatelliteState};
+use crate::validator::{precondition_failures, PreconditionFailure};
+
+//The parts of a SatelliteState that a step can change.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub enum Field {
+    Pointing,
+    PowerOn,
+    PowerAvail,
+    Calibrated,
+    Fuel,
+    FuelUsed,
+    DataCapacity,
+    HaveImage,
+    DataStored,
+}
+
+impl Field {
+    //The name of the corresponding predicate or function in the domain.
+    pub fn pddl_name(&self) -> &'static str {
+        match self {
+            Field::Pointing => "pointing",
+            Field::PowerOn => "power_on",
+            Field::PowerAvail => "power_avail",
+            Field::Calibrated => "calibrated",
+            Field::Fuel => "fuel",
+            Field::FuelUsed => "fuel-used",
+            Field::DataCapacity => "data_capacity",
+            Field::HaveImage => "have_image",
+            Field::DataStored => "data-stored",
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub enum FieldValue {
+    //A direction, or None for a satellite that is not pointing anywhere.
+    Object(Option<SatelliteEnum>),
+    Flag(bool),
+    Number(I40F24),
+}
+
+//One field of the state, for the objects it is about, before and after a step.
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub struct FieldChange {
+    pub field: Field,
+    pub objects: Vec<SatelliteEnum>,
+    pub before: FieldValue,
+    pub after: FieldValue,
+}
+
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub struct TraceStep {
+    pub index: usize,
+    pub step: SatelliteOperator<SatelliteEnum>,
+    //False for a step whose preconditions do not hold, which ends the trace.
+    pub applied: bool,
+    pub changes: Vec<FieldChange>,
+    //Why the step could not be applied; empty for applied steps.
+    pub failures: Vec<PreconditionFailure>,
+}
+
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub struct Trace {
+    pub steps: Vec<TraceStep>,
+    pub final_state: SatelliteState,
+}
+
+impl Trace {
+    pub fn completed(&self) -> bool {
+        self.steps.iter().all(|step| step.applied)
+    }
+}
+
+//Applies the plan one step at a time, recording what each step changes. Stops after the first
+//step that cannot be applied, recording the preconditions it breaks. As in validator::validate_plan,
+//the preconditions are checked first, since the operators assume objects the plan may not have.
+pub fn simulate(plan: &[SatelliteOperator<SatelliteEnum>], start: &SatelliteState) -> Trace {
+    let mut state = start.clone();
+    let mut steps = vec![];
+    for (index, step) in plan.iter().enumerate() {
+        let mut next = state.clone();
+        let failures = precondition_failures(&state, step);
+        let applied = failures.is_empty() && step.attempt_update(&mut next);
+        let changes = if applied { diff_states(&state, &next) } else { vec![] };
+        steps.push(TraceStep { index, step: *step, applied, changes, failures });
+        if !applied {
+            break;
+        }
+        state = next;
+    }
+    Trace { steps, final_state: state }
+}
+
+//Every field that differs between the two states.
+pub fn diff_states(before: &SatelliteState, after: &SatelliteState) -> Vec<FieldChange> {
+    let mut changes = vec![];
+    for satellite in keys(&before.pointing, &after.pointing) {
+        changes.extend(change(Field::Pointing, vec![satellite],
+                              FieldValue::Object(before.pointing.get(&satellite).copied()),
+                              FieldValue::Object(after.pointing.get(&satellite).copied())));
+    }
+    for instrument in either(&before.power_on, &after.power_on) {
+        changes.extend(change(Field::PowerOn, vec![instrument],
+                              FieldValue::Flag(before.power_on.contains(&instrument)),
+                              FieldValue::Flag(after.power_on.contains(&instrument))));
+    }
+    for satellite in keys(&before.power_avail, &after.power_avail) {
+        changes.extend(change(Field::PowerAvail, vec![satellite],
+                              FieldValue::Flag(before.power_avail.get(&satellite) == Some(&true)),
+                              FieldValue::Flag(after.power_avail.get(&satellite) == Some(&true))));
+    }
+    for instrument in either(&before.calibrated, &after.calibrated) {
+        changes.extend(change(Field::Calibrated, vec![instrument],
+                              FieldValue::Flag(before.calibrated.contains(&instrument)),
+                              FieldValue::Flag(after.calibrated.contains(&instrument))));
+    }
+    for satellite in keys(&before.fuel, &after.fuel) {
+        changes.extend(change(Field::Fuel, vec![satellite], number(before.fuel.get(&satellite)), number(after.fuel.get(&satellite))));
+    }
+    changes.extend(change(Field::FuelUsed, vec![], FieldValue::Number(before.fuel_used), FieldValue::Number(after.fuel_used)));
+    for satellite in keys(&before.data_capacity, &after.data_capacity) {
+        changes.extend(change(Field::DataCapacity, vec![satellite], number(before.data_capacity.get(&satellite)), number(after.data_capacity.get(&satellite))));
+    }
+    for (direction, mode) in before.have_image.union(&after.have_image) {
+        changes.extend(change(Field::HaveImage, vec![*direction, *mode],
+                              FieldValue::Flag(before.have_image.contains(&(*direction, *mode))),
+                              FieldValue::Flag(after.have_image.contains(&(*direction, *mode)))));
+    }
+    changes.extend(change(Field::DataStored, vec![], FieldValue::Number(before.total_data_stored), FieldValue::Number(after.total_data_stored)));
+    changes
+}
+
+fn change(field: Field, objects: Vec<SatelliteEnum>, before: FieldValue, after: FieldValue) -> Option<FieldChange> {
+    if before == after {
+        None
+    } else {
+        Some(FieldChange { field, objects, before, after })
+    }
+}
+
+fn number(value: Option<&I40F24>) -> FieldValue {
+    FieldValue::Number(value.copied().unwrap_or(I40F24::from_num(0)))
+}
+
+fn keys<V>(before: &BTreeMap<SatelliteEnum, V>, after: &BTreeMap<SatelliteEnum, V>) -> BTreeSet<SatelliteEnum> {
+    before.keys().chain(after.keys()).copied().collect()
+}
+
+fn either(before: &[SatelliteEnum], after: &[SatelliteEnum]) -> BTreeSet<SatelliteEnum> {
+    before.iter().chain(after.iter()).copied().collect()
+}
+
+impl NamedDisplay for FieldValue {
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldValue::Object(Some(item)) => item.fmt_named(table, f),
+            FieldValue::Object(None) => write!(f, "nothing"),
+            FieldValue::Flag(flag) => write!(f, "{}", flag),
+            FieldValue::Number(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl NamedDisplay for FieldChange {
+    //e.g. fuel satellite0: 212 -> 198
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.field.pddl_name())?;
+        for item in self.objects.iter() {
+            write!(f, " {}", table.named(item))?;
+        }
+        write!(f, ": {} -> {}", table.named(&self.before), table.named(&self.after))
+    }
+}
+
+//Writes the trace as a table with one row per change, e.g.
+//   1  switch_on instrument0 satellite0    power_on instrument0: false -> true
+pub fn write_trace_table<W: Write>(trace: &Trace, objects: &ObjectTable, out: &mut W) -> io::Result<()> {
+    let actions: Vec<String> = trace.steps.iter().map(|step| objects.named(&step.step).to_string()).collect();
+    let width = actions.iter().map(|action| action.len()).max().unwrap_or(0).max("action".len());
+    writeln!(out, "{:>4}  {:<width$}  {}", "step", "action", "changes", width = width)?;
+    for (step, action) in trace.steps.iter().zip(actions.iter()) {
+        let mut changes: Vec<String> = step.changes.iter().map(|change| objects.named(change).to_string()).collect();
+        if !step.applied {
+            changes = vec![String::from("NOT APPLICABLE")];
+            changes.extend(step.failures.iter().map(|failure| objects.named(failure).to_string()));
+        } else if changes.is_empty() {
+            changes = vec![String::from("no change")];
+        }
+        writeln!(out, "{:>4}  {:<width$}  {}", step.index + 1, action, changes[0], width = width)?;
+        for change in changes[1..].iter() {
+            writeln!(out, "{:>4}  {:<width$}  {}", "", "", change, width = width)?;
+        }
+    }
+    Ok(())
+}
+
+//Renders the trace as JSON: {"completed": bool, "steps": [{"step": 1, "action": "...", "applied": bool,
+//"changes": [{"field": "...", "objects": [...], "before": ..., "after": ...}], "failures": ["..."]}]}
+pub fn trace_to_json(trace: &Trace, objects: &ObjectTable) -> String {
+    let steps: Vec<String> = trace.steps.iter().map(|step| {
+        let changes: Vec<String> = step.changes.iter().map(|change| {
+            let names: Vec<String> = change.objects.iter().map(|item| json_string(&objects.named(item).to_string())).collect();
+            format!("{{\"field\": {}, \"objects\": [{}], \"before\": {}, \"after\": {}}}",
+                    json_string(change.field.pddl_name()), names.join(", "),
+                    json_value(&change.before, objects), json_value(&change.after, objects))
+        }).collect();
+        let failures: Vec<String> = step.failures.iter().map(|failure| json_string(&objects.named(failure).to_string())).collect();
+        format!("    {{\"step\": {}, \"action\": {}, \"applied\": {}, \"changes\": [{}], \"failures\": [{}]}}",
+                step.index + 1, json_string(&objects.named(&step.step).to_string()), step.applied, changes.join(", "), failures.join(", "))
+    }).collect();
+    format!("{{\n  \"completed\": {},\n  \"steps\": [\n{}\n  ]\n}}\n", trace.completed(), steps.join(",\n"))
+}
+
+fn json_value(value: &FieldValue, objects: &ObjectTable) -> String {
+    match value {
+        FieldValue::Object(Some(item)) => json_string(&objects.named(item).to_string()),
+        FieldValue::Object(None) => String::from("null"),
+        FieldValue::Flag(flag) => flag.to_string(),
+        FieldValue::Number(n) => n.to_string(),
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pddl_parser::make_satellite_problem_from_str;
+    use crate::plan_io::parse_plan;
+
+    const PROBLEM: &str = "(define (problem small) (:domain satellite)
+        (:objects satellite0 - satellite instrument0 - instrument image0 - mode star0 - direction star1 - direction)
+        (:init (on_board instrument0 satellite0) (supports instrument0 image0) (calibration_target instrument0 star0)
+               (power_avail satellite0) (pointing satellite0 star0)
+               (= (slew_time star0 star1) 10) (= (slew_time star1 star0) 10)
+               (= (fuel satellite0) 15) (= (data_capacity satellite0) 100) (= (data star1 image0) 20))
+        (:goal (and (have_image star1 image0))))";
+
+    #[test]
+    fn each_step_records_what_it_changed() {
+        let (start, _, objects) = make_satellite_problem_from_str(PROBLEM).unwrap();
+        let plan = parse_plan("(switch_on instrument0 satellite0)
+                               (turn_to satellite0 star1 star0)
+                               (turn_to satellite0 star0 star1)", &objects).unwrap();
+        let trace = simulate(&plan, &start);
+        assert!(!trace.completed());
+        assert_eq!(trace.steps.len(), 3);
+
+        let changes: Vec<String> = trace.steps[1].changes.iter().map(|change| objects.named(change).to_string()).collect();
+        assert_eq!(changes, vec!["pointing satellite0: star0 -> star1", "fuel satellite0: 15 -> 5", "fuel-used: 0 -> 10"]);
+        assert_eq!(trace.steps[0].changes.len(), 2);
+        assert!(!trace.steps[2].applied);
+
+        let json = trace_to_json(&trace, &objects);
+        assert!(json.contains("{\"field\": \"pointing\", \"objects\": [\"satellite0\"], \"before\": \"star0\", \"after\": \"star1\"}"));
+        assert!(json.contains("\"completed\": false"));
+        let parsed: serde_json::Value = serde_json::from_str(json.as_str()).unwrap();
+        assert_eq!(parsed["steps"][1]["action"], "turn_to satellite0 star1 star0");
+        assert_eq!(parsed["steps"][2]["applied"], false);
+        let mut table = vec![];
+        write_trace_table(&trace, &objects, &mut table).unwrap();
+        assert!(String::from_utf8(table).unwrap().contains("NOT APPLICABLE"));
+    }
+
+    #[test]
+    fn steps_for_unknown_satellites_fail_without_panicking() {
+        let problem = PROBLEM.replace("satellite0 - satellite", "satellite0 - satellite satellite1 - satellite");
+        let (start, _, objects) = make_satellite_problem_from_str(problem.as_str()).unwrap();
+        //satellite1 has no on_board facts, which the operators do not expect.
+        let plan = parse_plan("(switch_on instrument0 satellite1)", &objects).unwrap();
+        let trace = simulate(&plan, &start);
+        assert!(!trace.completed());
+        let failures: Vec<String> = trace.steps[0].failures.iter().map(|failure| objects.named(failure).to_string()).collect();
+        assert_eq!(failures, vec!["instrument0 is not on board satellite1", "satellite1 has no power available"]);
+
+        let mut table = vec![];
+        write_trace_table(&trace, &objects, &mut table).unwrap();
+        assert!(String::from_utf8(table).unwrap().contains("instrument0 is not on board satellite1"));
+        let parsed: serde_json::Value = serde_json::from_str(trace_to_json(&trace, &objects).as_str()).unwrap();
+        assert_eq!(parsed["steps"][0]["failures"][0], "instrument0 is not on board satellite1");
+    }
+
+    #[test]
+    fn json_strings_are_escaped() {
+        for text in ["plain", "a \"quoted\" name", "back\\slash", "two\nlines\tand\r\u{1}", "fuel-used"].iter() {
+            let parsed: String = serde_json::from_str(json_string(text).as_str()).unwrap();
+            assert_eq!(parsed.as_str(), *text);
+        }
+    }
+}
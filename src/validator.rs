@@ -0,0 +1,272 @@
+use std::fmt;
+
+use anyhop::Operator;
+use fixed::types::I40F24;
+use log::error;
+
+use crate::object_table::{NamedDisplay, ObjectTable};
+use crate::operators::{SatelliteEnum, SatelliteGoals, SatelliteOperator, SatelliteState, Semantics};
+use crate::operators::SatelliteOperator::{Calibrate, SwitchOff, SwitchOn, TakeImage, TurnTo};
+
+//A precondition of an action that does not hold in the state the action is applied to.
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub enum PreconditionFailure {
+    //satellite, the direction it needed to be pointing
+    NotPointing { satellite: SatelliteEnum, direction: SatelliteEnum },
+    //turn_to from a direction to itself
+    SameDirection { satellite: SatelliteEnum, direction: SatelliteEnum },
+    NotCalibrated { instrument: SatelliteEnum },
+    //new direction, previous direction
+    MissingSlewTime { new_direction: SatelliteEnum, previous_direction: SatelliteEnum },
+    InsufficientFuel { satellite: SatelliteEnum, needed: I40F24, available: I40F24 },
+    InsufficientCapacity { satellite: SatelliteEnum, needed: I40F24, available: I40F24 },
+    InstrumentNotOnboard { instrument: SatelliteEnum, satellite: SatelliteEnum },
+    PowerUnavailable { satellite: SatelliteEnum },
+    InstrumentNotPowered { instrument: SatelliteEnum },
+    ModeNotSupported { instrument: SatelliteEnum, mode: SatelliteEnum },
+    WrongCalibrationTarget { instrument: SatelliteEnum, direction: SatelliteEnum },
+}
+
+//A goal that does not hold at the end of the plan.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub enum UnmetGoal {
+    //direction, mode
+    HaveImage(SatelliteEnum, SatelliteEnum),
+    //satellite, direction
+    Pointing(SatelliteEnum, SatelliteEnum),
+}
+
+//The first step of a plan that cannot be applied, and every precondition it breaks.
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub struct StepFailure {
+    pub index: usize,
+    pub step: SatelliteOperator<SatelliteEnum>,
+    pub failures: Vec<PreconditionFailure>,
+}
+
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+pub struct ValidationReport {
+    pub steps_applied: usize,
+    pub failed_step: Option<StepFailure>,
+    //The state after the last step that could be applied.
+    pub final_state: SatelliteState,
+    pub unmet_goals: Vec<UnmetGoal>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.failed_step.is_none() && self.unmet_goals.is_empty()
+    }
+}
+
+//Applies the plan step by step, stopping at the first step whose preconditions do not hold.
+//Works on any plan, whether it came from the planner or from plan_io::read_plan.
+pub fn validate_plan(plan: &[SatelliteOperator<SatelliteEnum>], start: &SatelliteState, goal: &SatelliteGoals) -> ValidationReport {
+    let mut state = start.clone();
+    let mut failed_step = None;
+    for (index, step) in plan.iter().enumerate() {
+        let failures = precondition_failures(&state, step);
+        if !failures.is_empty() {
+            failed_step = Some(StepFailure { index, step: *step, failures });
+            break;
+        }
+        //The checks above mirror the operators, so this only fails if the two disagree.
+        if !step.attempt_update(&mut state) {
+            error!("{:?} was rejected although its preconditions hold", step);
+            failed_step = Some(StepFailure { index, step: *step, failures });
+            break;
+        }
+    }
+    let steps_applied = failed_step.as_ref().map_or(plan.len(), |failure| failure.index);
+    let unmet_goals = unmet_goals(&state, goal);
+    ValidationReport { steps_applied, failed_step, final_state: state, unmet_goals }
+}
+
+pub fn unmet_goals(state: &SatelliteState, goal: &SatelliteGoals) -> Vec<UnmetGoal> {
+    let mut unmet = vec![];
+    for (direction, mode) in goal.have_image.iter() {
+        if !state.have_image.contains(&(*direction, *mode)) {
+            unmet.push(UnmetGoal::HaveImage(*direction, *mode));
+        }
+    }
+    for (satellite, direction) in goal.pointing.iter() {
+        if state.pointing.get(satellite) != Some(direction) {
+            unmet.push(UnmetGoal::Pointing(*satellite, *direction));
+        }
+    }
+    unmet
+}
+
+//Every precondition of the step that does not hold in the state. Empty if the step can be applied.
+pub fn precondition_failures(state: &SatelliteState, step: &SatelliteOperator<SatelliteEnum>) -> Vec<PreconditionFailure> {
+    use PreconditionFailure::*;
+    let mut failures = vec![];
+    match *step {
+        TurnTo(satellite, new_direction, previous_direction) => {
+            check_pointing(state, satellite, previous_direction, &mut failures);
+            if new_direction == previous_direction {
+                failures.push(SameDirection { satellite, direction: new_direction });
+            }
+            if state.config.semantics == Semantics::Numeric {
+                match state.slew_time.get(&(new_direction, previous_direction)) {
+                    Some(needed) => {
+                        let available = state.fuel.get(&satellite).copied().unwrap_or(I40F24::from_num(0));
+                        if available < *needed || !state.fuel.contains_key(&satellite) {
+                            failures.push(InsufficientFuel { satellite, needed: *needed, available });
+                        }
+                    }
+                    None if new_direction != previous_direction => failures.push(MissingSlewTime { new_direction, previous_direction }),
+                    None => {}
+                }
+            }
+        }
+        SwitchOn(instrument, satellite) => {
+            check_onboard(state, instrument, satellite, &mut failures);
+            if state.power_avail.get(&satellite) != Some(&true) {
+                failures.push(PowerUnavailable { satellite });
+            }
+        }
+        SwitchOff(instrument, satellite) => {
+            check_onboard(state, instrument, satellite, &mut failures);
+            check_powered(state, instrument, &mut failures);
+        }
+        Calibrate(satellite, instrument, direction) => {
+            check_onboard(state, instrument, satellite, &mut failures);
+            if state.calibration_target.get(&instrument) != Some(&direction) {
+                failures.push(WrongCalibrationTarget { instrument, direction });
+            }
+            check_pointing(state, satellite, direction, &mut failures);
+            check_powered(state, instrument, &mut failures);
+        }
+        TakeImage(satellite, direction, instrument, mode) => {
+            if !state.calibrated.contains(&instrument) {
+                failures.push(NotCalibrated { instrument });
+            }
+            check_onboard(state, instrument, satellite, &mut failures);
+            if !state.does_instrument_support_mode(&instrument, &mode) {
+                failures.push(ModeNotSupported { instrument, mode });
+            }
+            check_powered(state, instrument, &mut failures);
+            check_pointing(state, satellite, direction, &mut failures);
+            let needed = state.satellite_data_stored.get(&(direction, mode)).copied().unwrap_or(I40F24::from_num(0));
+            let available = state.data_capacity.get(&satellite).copied().unwrap_or(I40F24::from_num(0));
+            if available < needed {
+                failures.push(InsufficientCapacity { satellite, needed, available });
+            }
+        }
+    }
+    failures
+}
+
+fn check_pointing(state: &SatelliteState, satellite: SatelliteEnum, direction: SatelliteEnum, failures: &mut Vec<PreconditionFailure>) {
+    if state.pointing.get(&satellite) != Some(&direction) {
+        failures.push(PreconditionFailure::NotPointing { satellite, direction });
+    }
+}
+
+fn check_onboard(state: &SatelliteState, instrument: SatelliteEnum, satellite: SatelliteEnum, failures: &mut Vec<PreconditionFailure>) {
+    if !state.onboard.get(&satellite).map_or(false, |instruments| instruments.contains(&instrument)) {
+        failures.push(PreconditionFailure::InstrumentNotOnboard { instrument, satellite });
+    }
+}
+
+fn check_powered(state: &SatelliteState, instrument: SatelliteEnum, failures: &mut Vec<PreconditionFailure>) {
+    if !state.power_on.contains(&instrument) {
+        failures.push(PreconditionFailure::InstrumentNotPowered { instrument });
+    }
+}
+
+impl NamedDisplay for PreconditionFailure {
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result {
+        use PreconditionFailure::*;
+        match self {
+            NotPointing { satellite, direction } => write!(f, "{} is not pointing at {}", table.named(satellite), table.named(direction)),
+            SameDirection { satellite, direction } => write!(f, "{} is already pointing at {}", table.named(satellite), table.named(direction)),
+            NotCalibrated { instrument } => write!(f, "{} is not calibrated", table.named(instrument)),
+            MissingSlewTime { new_direction, previous_direction } => write!(f, "there is no slew_time from {} to {}", table.named(previous_direction), table.named(new_direction)),
+            InsufficientFuel { satellite, needed, available } => write!(f, "{} needs {} fuel but has {}", table.named(satellite), needed, available),
+            InsufficientCapacity { satellite, needed, available } => write!(f, "{} needs {} data capacity but has {}", table.named(satellite), needed, available),
+            InstrumentNotOnboard { instrument, satellite } => write!(f, "{} is not on board {}", table.named(instrument), table.named(satellite)),
+            PowerUnavailable { satellite } => write!(f, "{} has no power available", table.named(satellite)),
+            InstrumentNotPowered { instrument } => write!(f, "{} is not switched on", table.named(instrument)),
+            ModeNotSupported { instrument, mode } => write!(f, "{} does not support {}", table.named(instrument), table.named(mode)),
+            WrongCalibrationTarget { instrument, direction } => write!(f, "{} is not the calibration target of {}", table.named(direction), table.named(instrument)),
+        }
+    }
+}
+
+impl NamedDisplay for UnmetGoal {
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnmetGoal::HaveImage(direction, mode) => write!(f, "(have_image {} {})", table.named(direction), table.named(mode)),
+            UnmetGoal::Pointing(satellite, direction) => write!(f, "(pointing {} {})", table.named(satellite), table.named(direction)),
+        }
+    }
+}
+
+impl NamedDisplay for ValidationReport {
+    fn fmt_named(&self, table: &ObjectTable, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(failure) = self.failed_step.as_ref() {
+            writeln!(f, "Step {} ({}) cannot be applied:", failure.index + 1, table.named(&failure.step))?;
+            for precondition in failure.failures.iter() {
+                writeln!(f, "  {}", table.named(precondition))?;
+            }
+        }
+        if !self.unmet_goals.is_empty() {
+            writeln!(f, "Goals not met after {} steps:", self.steps_applied)?;
+            for goal in self.unmet_goals.iter() {
+                writeln!(f, "  {}", table.named(goal))?;
+            }
+        }
+        if self.is_valid() {
+            writeln!(f, "Plan is valid ({} steps, {} fuel used)", self.steps_applied, self.final_state.fuel_used)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pddl_parser::make_satellite_problem_from_str;
+    use crate::plan_io::parse_plan;
+
+    const PROBLEM: &str = "(define (problem small) (:domain satellite)
+        (:objects satellite0 - satellite instrument0 - instrument image0 - mode star0 - direction star1 - direction)
+        (:init (on_board instrument0 satellite0) (supports instrument0 image0) (calibration_target instrument0 star0)
+               (power_avail satellite0) (pointing satellite0 star0)
+               (= (slew_time star0 star1) 10) (= (slew_time star1 star0) 10)
+               (= (fuel satellite0) 15) (= (data_capacity satellite0) 100) (= (data star1 image0) 20)
+               (= (data-stored) 0) (= (fuel-used) 0))
+        (:goal (and (have_image star1 image0) (pointing satellite0 star0))))";
+
+    #[test]
+    fn reports_the_first_failing_step() {
+        let (start, goal, objects) = make_satellite_problem_from_str(PROBLEM).unwrap();
+        let plan = parse_plan("(switch_on instrument0 satellite0)
+                               (turn_to satellite0 star1 star0)
+                               (take_image satellite0 star1 instrument0 image0)", &objects).unwrap();
+        let report = validate_plan(&plan, &start, &goal);
+        let failure = report.failed_step.as_ref().unwrap();
+        assert_eq!(failure.index, 2);
+        assert_eq!(failure.failures, vec![PreconditionFailure::NotCalibrated { instrument: objects.get("instrument0").unwrap() }]);
+        assert_eq!(report.final_state.fuel_used, I40F24::from_num(10));
+        assert_eq!(report.unmet_goals.len(), 2);
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn running_out_of_fuel_is_reported() {
+        let (start, goal, objects) = make_satellite_problem_from_str(PROBLEM).unwrap();
+        let plan = parse_plan("(switch_on instrument0 satellite0)
+                               (calibrate satellite0 instrument0 star0)
+                               (turn_to satellite0 star1 star0)
+                               (take_image satellite0 star1 instrument0 image0)
+                               (turn_to satellite0 star0 star1)", &objects).unwrap();
+        let report = validate_plan(&plan, &start, &goal);
+        assert_eq!(report.steps_applied, 4);
+        assert_eq!(report.failed_step.unwrap().failures, vec![PreconditionFailure::InsufficientFuel {
+            satellite: objects.get("satellite0").unwrap(), needed: I40F24::from_num(10), available: I40F24::from_num(5) }]);
+        assert_eq!(report.unmet_goals, vec![UnmetGoal::Pointing(objects.get("satellite0").unwrap(), objects.get("star0").unwrap())]);
+    }
+}